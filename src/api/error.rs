@@ -8,14 +8,17 @@ use axum::response::{IntoResponse, Response};
 /// api routes directly.
 pub struct IntoResponseError {
     pub msg: String,
-    pub status: StatusCode
+    pub status: StatusCode,
+    /// Machine readable reason of the error, sent along the message if present.
+    pub code: Option<&'static str>
 }
 
 impl IntoResponseError {
     pub fn new(msg: impl ToString) -> Self {
         Self {
             msg: msg.to_string(),
-            status: StatusCode::INTERNAL_SERVER_ERROR
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            code: None
         }
     }
 
@@ -23,6 +26,11 @@ impl IntoResponseError {
         self.status = status;
         self
     }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
 }
 
 impl<T: Error> From<T> for IntoResponseError {
@@ -45,13 +53,18 @@ impl Display for IntoResponseError {
 
 impl IntoResponse for IntoResponseError {
     fn into_response(self) -> Response {
+        let body = match self.code {
+            Some(code) => format!(r#"{{ "message": "{self}", "code": "{code}" }}"#),
+            None => format!(r#"{{ "message": "{self}" }}"#)
+        };
+
         Response::builder()
             .status(self.status)
             .header(
                 axum::http::header::CONTENT_TYPE,
                 "application/json"
             )
-            .body(Body::from(format!(r#"{{ "message": "{self}" }}"#)))
+            .body(Body::from(body))
             .unwrap()
     }
 }
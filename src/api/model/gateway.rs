@@ -1,4 +1,6 @@
 use crate::api::model::track::Track;
+use crate::config::IdleAction;
+use crate::playback::player::timer::SleepTimer;
 use crate::api::model;
 
 /// Events sent via websocket to clients.
@@ -42,6 +44,32 @@ pub enum OutgoingEvent {
         error: String,
         /// The track itself.
         track: Track
+    },
+    /// Autoplay enqueued tracks after the queue ran out of them.
+    Autoplay {
+        /// The enqueued tracks.
+        tracks: Vec<Track>
+    },
+    /// The sleep timer stopped the playback, contains the settings of the timer.
+    SleepTimer(SleepTimer),
+    /// The player has been idle for longer than the configured timeout.
+    PlayerIdle {
+        /// Time the player has been idle, in milliseconds.
+        idle: u64,
+        /// What happens to the player because of it.
+        action: IdleAction
+    },
+    /// An overlay started playing on top of the queue.
+    OverlayStart(Track),
+    /// An overlay ended, either naturally, manually or because of an error.
+    OverlayEnd {
+        /// Whether the overlay was stopped manually.
+        stopped: bool,
+        /// The error message, if the overlay had an error on playback.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+        /// The overlay itself.
+        track: Track
     }
 }
 
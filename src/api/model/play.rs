@@ -1,6 +1,8 @@
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use crate::api::model::track::Track;
+use crate::playback::player::overlay::Ducking;
 
 /// Sources that can be used to play from.
 #[derive(Deserialize, Serialize, Clone)]
@@ -41,6 +43,15 @@ impl PlaySource {
         }
     }
 
+    /// The link of the source, if it is provided by link.
+    pub fn link(&self) -> Option<&str> {
+        match self {
+            Self::Link {link, ..} => Some(link),
+            Self::Http {link, ..} => Some(link),
+            Self::Bytes {..} => None
+        }
+    }
+
     pub fn track(&mut self) -> Option<Track> {
         match self {
             Self::Link {..} => None,
@@ -58,6 +69,143 @@ pub struct PlayOptions {
     /// if this is set to `true`, the provided track will play at arrival, and the
     /// currently playing one will be resumed when it ends.
     pub force_play: bool,
-    /// The track source.
-    pub source: PlaySource
+    /// The track source, links to playlists and albums are expanded into their tracks.
+    #[serde(default)]
+    pub source: Option<PlaySource>,
+    /// Sources to enqueue in order, instead of a single `source`.
+    #[serde(default)]
+    pub sources: Vec<PlaySource>,
+    /// Data sent back in the tracks, for every track if multiple are enqueued.
+    #[serde(default)]
+    pub user_data: Option<Value>,
+    /// Position in milliseconds the track starts playing from, only for single tracks.
+    #[serde(default)]
+    pub start_time: Option<u64>,
+    /// Position in milliseconds the track ends at, only for single tracks.
+    #[serde(default)]
+    pub end_time: Option<u64>
+}
+
+impl PlayOptions {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if let (Some(start), Some(end)) = (self.start_time, self.end_time) {
+            if end <= start {
+                return Err("`end_time` must be after `start_time`");
+            }
+        }
+
+        if !self.sources.is_empty() {
+            validate_list(self.force_play, self.start_time, self.end_time)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Options provided when inserting a track in the queue.
+#[derive(Deserialize)]
+pub struct InsertOptions {
+    pub source: PlaySource,
+    /// Data sent back in the track, also used to find its requester for the queue policy.
+    #[serde(default)]
+    pub user_data: Option<Value>
+}
+
+/// Checks the play options that only apply to single tracks aren't provided, for requests
+/// that enqueue a list of tracks.
+pub fn validate_list(force_play: bool, start_time: Option<u64>, end_time: Option<u64>) -> Result<(), &'static str> {
+    if force_play {
+        return Err("Lists of tracks can't be force played");
+    }
+
+    if start_time.is_some() || end_time.is_some() {
+        return Err("`start_time` and `end_time` can't be used with lists of tracks");
+    }
+
+    Ok(())
+}
+
+fn default_overlay_volume() -> u16 {
+    100
+}
+
+/// Options provided when playing a sound on top of the queue.
+#[derive(Deserialize)]
+pub struct OverlayOptions {
+    pub source: PlaySource,
+    /// Volume of the overlay, from 0 to 512.
+    #[serde(default = "default_overlay_volume")]
+    pub volume: u16,
+    /// Lowers the volume of the music while the overlay plays.
+    #[serde(default)]
+    pub duck: Option<Ducking>,
+    /// Data sent back in the overlay events.
+    #[serde(default)]
+    pub user_data: Option<Value>
+}
+
+impl OverlayOptions {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.volume > 512 {
+            return Err("Volume must be an integer between 0 and 512");
+        }
+
+        self.duck.as_ref().map_or(Ok(()), Ducking::validate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn options(value: Value) -> PlayOptions {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn link(link: &str) -> Value {
+        json!({ "type": "link", "data": { "link": link } })
+    }
+
+    #[test]
+    fn single_sources_can_be_force_played() {
+        let single = options(json!({ "force_play": true, "source": link("a") }));
+        assert!(single.validate().is_ok());
+    }
+
+    #[test]
+    fn lists_of_sources_cant_be_force_played() {
+        let list = options(json!({ "force_play": false, "sources": [link("a"), link("b")] }));
+        assert!(list.validate().is_ok());
+
+        let forced = options(json!({ "force_play": true, "sources": [link("a"), link("b")] }));
+        assert!(forced.validate().is_err());
+
+        assert!(validate_list(false, None, None).is_ok());
+        assert!(validate_list(true, None, None).is_err());
+    }
+
+    #[test]
+    fn clips_must_end_after_they_start() {
+        let clip = options(json!({ "force_play": false, "source": link("a"), "start_time": 1000, "end_time": 5000 }));
+        assert!(clip.validate().is_ok());
+
+        let empty = options(json!({ "force_play": false, "source": link("a"), "start_time": 5000, "end_time": 5000 }));
+        assert!(empty.validate().is_err());
+
+        let reversed = options(json!({ "force_play": false, "source": link("a"), "start_time": 5000, "end_time": 1000 }));
+        assert!(reversed.validate().is_err());
+    }
+
+    #[test]
+    fn lists_of_sources_cant_be_clipped() {
+        let start = options(json!({ "force_play": false, "sources": [link("a")], "start_time": 1000 }));
+        assert!(start.validate().is_err());
+
+        let end = options(json!({ "force_play": false, "sources": [link("a")], "end_time": 1000 }));
+        assert!(end.validate().is_err());
+
+        assert!(validate_list(false, Some(1000), None).is_err());
+        assert!(validate_list(false, None, Some(1000)).is_err());
+    }
 }
@@ -1,8 +1,13 @@
 use std::num::NonZeroU64;
 use std::time::Duration;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use crate::playback::filters::FilterConfig;
+use crate::playback::filters::normalization::Normalization;
+use crate::playback::player::policy::QueuePolicy;
+use crate::playback::player::timer::SleepTimer;
+use crate::playback::player::transition::Transition;
 use super::track::Track;
 
 /// Serializable player object.
@@ -12,8 +17,100 @@ pub struct Player {
     pub channel_id: Option<NonZeroU64>,
     pub paused: bool,
     pub volume: u8,
+    pub filters: Vec<FilterConfig>,
+    pub normalization: Normalization,
+    pub transition: Transition,
+    pub shuffle: bool,
+    pub autoplay: bool,
+    /// The sleep timer with the time or tracks left before it fires.
+    pub sleep_timer: Option<SleepTimer>,
+    pub queue_policy: QueuePolicy,
     pub currently_playing: Option<Track>,
-    pub queue: Vec<Track>
+    /// Position of the current track in milliseconds, not affected by the timescale filter.
+    pub position: Option<u128>,
+    /// Number of upcoming tracks.
+    pub queue_length: usize,
+    /// The upcoming tracks, left out if requested with [`PlayerQuery`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue: Option<Vec<Track>>
+}
+
+fn yes() -> bool {
+    true
+}
+
+/// Query parameters of the routes that return players.
+#[derive(Deserialize, Debug)]
+pub struct PlayerQuery {
+    /// Whether to include the upcoming tracks in the players.
+    #[serde(default = "yes")]
+    pub queue: bool
+}
+
+impl Default for PlayerQuery {
+    fn default() -> Self {
+        Self {
+            queue: true
+        }
+    }
+}
+
+/// Maximum number of tracks returned by a single page of the queue.
+const MAX_PAGE: usize = 1000;
+
+fn default_limit() -> usize {
+    100
+}
+
+/// Query parameters of the queue listing route.
+#[derive(Deserialize, Debug)]
+pub struct QueuePageQuery {
+    /// Index of the first track of the page.
+    #[serde(default)]
+    pub offset: usize,
+    /// Maximum number of tracks of the page.
+    #[serde(default = "default_limit")]
+    pub limit: usize
+}
+
+impl QueuePageQuery {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.limit == 0 || self.limit > MAX_PAGE {
+            return Err("Limit must be between 1 and 1000");
+        }
+
+        Ok(())
+    }
+}
+
+/// A page of the upcoming tracks of a player.
+#[derive(Serialize, Debug)]
+pub struct QueuePage {
+    /// Number of upcoming tracks.
+    pub total: usize,
+    pub offset: usize,
+    pub tracks: Vec<Track>
+}
+
+/// Maximum fade duration accepted by the routes, in milliseconds.
+const MAX_FADE: u64 = 10_000;
+
+/// Query parameters of the routes that can fade the audio.
+#[derive(Deserialize, Debug, Default)]
+pub struct FadeQuery {
+    /// Duration of the fade in milliseconds, the change is instant if not provided.
+    #[serde(default)]
+    pub fade: u64
+}
+
+impl FadeQuery {
+    pub fn duration(&self) -> Result<Duration, &'static str> {
+        if self.fade > MAX_FADE {
+            return Err("Fade duration must be between 0 and 10000ms");
+        }
+
+        Ok(Duration::from_millis(self.fade))
+    }
 }
 
 #[derive(Serialize, Debug)]
@@ -1,10 +1,15 @@
 use std::time::Duration;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use songbird::input::AuxMetadata;
+use uuid::Uuid;
 
 /// Serializable songbird track.
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Track {
+    /// Id of the queue entry of the track, only present in tracks sent by the server.
+    #[serde(default)]
+    pub id: Option<Uuid>,
     pub track: Option<String>,
     pub artist: Option<String>,
     pub album: Option<String>,
@@ -13,11 +18,21 @@ pub struct Track {
     pub source_url: Option<String>,
     pub title: Option<String>,
     pub thumbnail: Option<String>,
+    /// Data provided by the client when playing the track.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_data: Option<Value>,
+    /// Position in milliseconds the track starts playing from, if it was played as a clip.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<u64>,
+    /// Position in milliseconds the track ends at, if it was played as a clip.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<u64>
 }
 
 impl From<AuxMetadata> for Track {
     fn from(meta: AuxMetadata) -> Self {
         Self {
+            id: None,
             track: meta.track,
             artist: meta.artist,
             album: meta.album,
@@ -25,7 +40,10 @@ impl From<AuxMetadata> for Track {
             duration: meta.duration.map(|d| d.as_millis()),
             source_url: meta.source_url,
             title: meta.title,
-            thumbnail: meta.thumbnail
+            thumbnail: meta.thumbnail,
+            user_data: None,
+            start_time: None,
+            end_time: None
         }
     }
 }
@@ -33,6 +51,7 @@ impl From<AuxMetadata> for Track {
 impl From<&AuxMetadata> for Track {
     fn from(value: &AuxMetadata) -> Self {
         Self {
+            id: None,
             track: value.track.clone(),
             artist: value.artist.clone(),
             album: value.album.clone(),
@@ -40,7 +59,10 @@ impl From<&AuxMetadata> for Track {
             duration: value.duration.map(|d| d.as_millis()),
             source_url: value.source_url.clone(),
             title: value.title.clone(),
-            thumbnail: value.thumbnail.clone()
+            thumbnail: value.thumbnail.clone(),
+            user_data: None,
+            start_time: None,
+            end_time: None
         }
     }
 }
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+use std::num::NonZeroU64;
+use axum::extract::{Path, State as AxumState};
+use axum::http::StatusCode;
+use axum::Json;
+use uuid::Uuid;
+use crate::api::error::IntoResponseError;
+use crate::api::extractors::player::PlayerExtractor;
+use crate::api::state::State;
+use crate::playback::filters::FilterConfig;
+
+fn validate(filter: &FilterConfig) -> Result<(), IntoResponseError> {
+    filter.validate()
+        .map_err(|e| IntoResponseError::new(e).with_status(StatusCode::BAD_REQUEST))
+}
+
+/// Retrieves the filters applied to the provided player.
+pub async fn get(PlayerExtractor {player, ..}: PlayerExtractor) -> Json<Vec<FilterConfig>> {
+    Json(player.lock().await.filters.get())
+}
+
+/// Sets a single filter on the provided player, replacing the existing one of the same kind.
+pub async fn set(
+    PlayerExtractor {player, ..}: PlayerExtractor,
+    Json(filter): Json<FilterConfig>
+) -> Result<Json<Vec<FilterConfig>>, IntoResponseError> {
+    validate(&filter)?;
+
+    let lock = player.lock().await;
+    lock.filters.set(filter);
+
+    Ok(Json(lock.filters.get()))
+}
+
+/// Replaces all the filters of the provided player.
+pub async fn replace(
+    PlayerExtractor {player, ..}: PlayerExtractor,
+    Json(filters): Json<Vec<FilterConfig>>
+) -> Result<Json<Vec<FilterConfig>>, IntoResponseError> {
+    for filter in filters.iter() {
+        validate(filter)?;
+    }
+
+    let lock = player.lock().await;
+    lock.filters.replace(filters);
+
+    Ok(Json(lock.filters.get()))
+}
+
+/// Removes all the filters of the provided player.
+pub async fn clear(PlayerExtractor {player, ..}: PlayerExtractor) {
+    player.lock().await.filters.clear();
+}
+
+/// Lists the filter presets defined in the server configuration.
+pub async fn presets(AxumState(state): AxumState<State>) -> Json<BTreeMap<String, Vec<FilterConfig>>> {
+    Json(state.filter_options.presets.clone())
+}
+
+/// Applies the filters of a preset to the provided player, replacing the existing filters
+/// of the same kinds.
+pub async fn apply_preset(
+    AxumState(state): AxumState<State>,
+    Path((session, guild, preset)): Path<(Uuid, NonZeroU64, String)>
+) -> Result<Json<Vec<FilterConfig>>, IntoResponseError> {
+    let PlayerExtractor {player, ..} = PlayerExtractor::from_id(session, &state, guild)?;
+
+    let Some(filters) = state.filter_options.presets.get(&preset) else {
+        return Err(IntoResponseError::new("The provided preset does not exist")
+            .with_status(StatusCode::NOT_FOUND));
+    };
+
+    let lock = player.lock().await;
+    lock.filters.merge(filters.iter().cloned());
+
+    Ok(Json(lock.filters.get()))
+}
+
+/// Removes a single filter of the provided player.
+pub async fn remove(
+    AxumState(state): AxumState<State>,
+    Path((session, guild, filter)): Path<(Uuid, NonZeroU64, String)>
+) -> Result<Json<Vec<FilterConfig>>, IntoResponseError> {
+    let PlayerExtractor {player, ..} = PlayerExtractor::from_id(session, &state, guild)?;
+    let lock = player.lock().await;
+
+    if !lock.filters.remove(&filter) {
+        return Err(IntoResponseError::new("The player doesn't have the provided filter")
+            .with_status(StatusCode::NOT_FOUND));
+    }
+
+    Ok(Json(lock.filters.get()))
+}
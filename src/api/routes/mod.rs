@@ -8,6 +8,7 @@ mod info;
 mod search;
 mod player;
 mod queue;
+mod filters;
 
 /// API routes.
 pub fn get_router() -> Router<State> {
@@ -17,20 +18,49 @@ pub fn get_router() -> Router<State> {
             .route("/:session", get(info::info))
         )
         .route("/metrics", get(prometheus::prometheus_metrics))
+        .route("/presets", get(filters::presets))
         .nest("/search", search::get_router())
         .nest("/:session", Router::new()
             .nest("/players/:guild", Router::new()
                 .route("/update", patch(player::update))
+                .route("/leave", patch(player::leave))
                 .route("/info", get(player::info))
                 .route("/play", post(player::play).layer(DefaultBodyLimit::disable()))
+                .route("/overlay", post(player::overlay)
+                    .delete(player::stop_overlays)
+                    .layer(DefaultBodyLimit::disable())
+                )
                 .route("/pause", patch(player::pause))
                 .route("/resume", patch(player::resume))
                 .route("/set_volume/:volume", patch(player::volume))
                 .route("/seek/:millis", patch(player::seek))
+                .route("/normalization", patch(player::normalization))
+                .route("/transition", patch(player::transition))
+                .route("/autoplay", patch(player::autoplay))
+                .route("/sleep_timer", patch(player::sleep_timer).delete(player::cancel_sleep_timer))
+                .nest("/filters", Router::new()
+                    .route("/", get(filters::get)
+                        .patch(filters::set)
+                        .put(filters::replace)
+                        .delete(filters::clear)
+                    )
+                    .route("/:filter", delete(filters::remove))
+                    .route("/presets/:preset", patch(filters::apply_preset))
+                )
                 .nest("/queue", Router::new()
+                    .route("/", get(queue::list))
                     .route("/skip", patch(queue::skip))
+                    .route("/skip_to/:index", patch(queue::skip_to))
                     .route("/clear", delete(queue::clear))
                     .route("/repeat", patch(queue::repeat))
+                    .route("/shuffle", patch(queue::shuffle))
+                    .route("/previous", patch(queue::previous))
+                    .route("/history", get(queue::history))
+                    .route("/policy", patch(queue::policy))
+                    .route("/insert/:index", post(queue::insert).layer(DefaultBodyLimit::disable()))
+                    .route("/swap/:first/:second", patch(queue::swap))
+                    .route("/:id", delete(queue::remove))
+                    .route("/:id/move/:index", patch(queue::move_to))
                 )
             )
         )
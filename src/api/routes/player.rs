@@ -1,11 +1,13 @@
 use std::num::NonZeroU64;
 use std::sync::Arc;
+use std::time::Duration;
 use axum::body::Body;
-use axum::extract::Path;
+use axum::extract::{Path, Query};
 use axum::http::StatusCode;
 use axum::extract::State as AxumState;
 use axum::Json;
 use axum::response::{IntoResponse, Response};
+use songbird::input::Input;
 use tracing::info;
 use uuid::Uuid;
 use crate::api::error::IntoResponseError;
@@ -13,15 +15,23 @@ use crate::api::error::IntoResponseError;
 use crate::api::extractors::player::PlayerExtractor;
 use crate::api::extractors::session::SessionWithGuildExtractor;
 use crate::api::model::connection::DeserializableConnectionInfo;
-use crate::api::model::play::PlayOptions;
-use crate::api::model::player::{Player, SeekJson};
+use crate::api::model::play::{OverlayOptions, PlayOptions, PlaySource, validate_list};
+use crate::api::model::player::{FadeQuery, Player, PlayerQuery, SeekJson};
 use crate::api::model::track::Track;
 use crate::api::state::State;
+use crate::playback::filters::normalization::Normalization;
 use crate::playback::metadata::TrackMetadata;
+use crate::playback::player::timer::SleepTimer;
+use crate::playback::player::transition::Transition;
+use crate::playback::handle::PendingTrack;
+use crate::source::{ListedTrack, Playable};
 
 /// Retrieves information about the given player.
-pub async fn info(PlayerExtractor {player, ..}: PlayerExtractor) -> Json<Player> {
-    Json(player.lock().await.as_json())
+pub async fn info(
+    PlayerExtractor {player, ..}: PlayerExtractor,
+    Query(query): Query<PlayerQuery>
+) -> Json<Player> {
+    Json(player.lock().await.as_json(query.queue))
 }
 
 /// Tries to connect to the provided channel, this route returns a response immediately,
@@ -48,60 +58,230 @@ pub async fn update(
         .unwrap())
 }
 
+/// Leaves the voice channel keeping the player, its queue, position and filters. The playback
+/// is paused until the player connects again with [`update`].
+pub async fn leave(
+    SessionWithGuildExtractor {session, guild}: SessionWithGuildExtractor
+) -> Result<Response, IntoResponseError> {
+    if !session.playback.leave_player(guild, &session).await {
+        return Err(IntoResponseError::new("The player does not exist")
+            .with_status(StatusCode::BAD_REQUEST));
+    }
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .body(Body::empty())
+        .unwrap())
+}
+
+/// Gets the input and metadata of the provided source.
+pub(super) async fn resolve(
+    state: &State,
+    source: &mut PlaySource,
+    guild: NonZeroU64
+) -> Result<(Input, TrackMetadata), IntoResponseError> {
+    let Playable { input, meta, loudness } = state.sources.playable_for(source).await?;
+
+    Ok((input, TrackMetadata {
+        loudness,
+        ..TrackMetadata::new(meta, guild.get())
+    }))
+}
+
+/// Plays or enqueues a single track, or enqueues a list of tracks if the source is a link
+/// to a playlist or an album, or multiple sources are provided. Requests breaking the queue
+/// policy of the player are rejected, tracks of a list already in queue are left out.
 pub async fn play(
     AxumState(state): AxumState<State>,
     PlayerExtractor {player, guild}: PlayerExtractor,
-    Json(mut options): Json<PlayOptions>
-) -> Result<Json<Track>, IntoResponseError> {
+    Json(options): Json<PlayOptions>
+) -> Result<Response, IntoResponseError> {
     info!("Received play request");
+    options.validate()
+        .map_err(|e| IntoResponseError::new(e).with_status(StatusCode::BAD_REQUEST))?;
+
     let ticket = player.ticket();
 
-    let (source, aux_meta) = state.sources.playable_for(&mut options.source).await
-        .map(|playable| (playable.input, playable.meta))?;
+    let PlayOptions { force_play, source, sources, user_data, start_time, end_time } = options;
 
-    let meta = TrackMetadata {
-        guild: guild.get(),
-        metadata: aux_meta
-    };
+    let tracks = match (source, sources.is_empty()) {
+        (Some(mut source), true) => {
+            // Checked before fetching the tracks of the list, which may take many requests.
+            if state.sources.is_list(&source) {
+                validate_list(force_play, start_time, end_time)
+                    .map_err(|e| IntoResponseError::new(e).with_status(StatusCode::BAD_REQUEST))?;
+            }
 
-    let track = meta.track();
+            match state.sources.tracks_of(&source).await? {
+                Some(tracks) => tracks,
+                None => {
+                    // Tracks that won't be played or preloaded right away are resolved once they
+                    // are about to become the next track.
+                    if !force_play && player.lock().await.queue.next.is_some() {
+                        let mut lock = ticket.wait().await;
+                        lock.policy.check(&lock.queue, &source, user_data.as_ref())?;
+
+                        let mut pending = PendingTrack::new(source);
+                        pending.track.user_data = user_data;
+                        pending.track.start_time = start_time;
+                        pending.track.end_time = end_time;
+
+                        let track = lock.enqueue_pending(pending).await;
+                        return Ok(Json(track).into_response());
+                    }
+
+                    let (input, mut meta) = resolve(&state, &mut source, guild).await?;
+
+                    let mut lock = ticket.wait().await;
+                    lock.policy.check(&lock.queue, &source, user_data.as_ref())?;
+
+                    meta.user_data = user_data;
+                    meta.start_time = start_time.map(Duration::from_millis);
+                    meta.end_time = end_time.map(Duration::from_millis);
+                    let track = meta.track();
+
+                    if force_play {
+                        lock.play_now(input, meta, source).await;
+                    } else {
+                        lock.enqueue(input, meta, source).await;
+                    }
+
+                    return Ok(Json(track).into_response());
+                }
+            }
+        },
+        (None, false) => {
+            let mut tracks = Vec::with_capacity(sources.len());
+
+            for source in sources {
+                match state.sources.tracks_of(&source).await? {
+                    Some(listed) => tracks.extend(listed),
+                    None => tracks.push(ListedTrack::from(source))
+                }
+            }
+
+            tracks
+        },
+        (None, true) => return Err(IntoResponseError::new("Either `source` or `sources` must be provided")
+            .with_status(StatusCode::BAD_REQUEST)),
+        (Some(_), false) => return Err(IntoResponseError::new("`source` and `sources` are mutually exclusive")
+            .with_status(StatusCode::BAD_REQUEST))
+    };
 
     let mut lock = ticket.wait().await;
-    if options.force_play {
-        lock.play_now(source, meta, options.source).await;
-    } else {
-        lock.enqueue(source, meta, options.source).await;
+    let tracks = lock.policy.filter(&lock.queue, tracks, user_data.as_ref())?;
+    let mut enqueued = Vec::with_capacity(tracks.len());
+
+    for ListedTrack { source, track } in tracks {
+        let track = Track {
+            user_data: user_data.clone(),
+            ..track
+        };
+
+        enqueued.push(lock.enqueue_pending(PendingTrack::with_track(source, track)).await);
     }
 
+    Ok(Json(enqueued).into_response())
+}
+
+/// Plays a short sound on top of the queue without changing it, lowering the volume of the
+/// music while it plays if requested.
+pub async fn overlay(
+    AxumState(state): AxumState<State>,
+    PlayerExtractor {player, guild}: PlayerExtractor,
+    Json(options): Json<OverlayOptions>
+) -> Result<Json<Track>, IntoResponseError> {
+    options.validate()
+        .map_err(|e| IntoResponseError::new(e).with_status(StatusCode::BAD_REQUEST))?;
+
+    let OverlayOptions { mut source, volume, duck, user_data } = options;
+
+    let (input, mut meta) = resolve(&state, &mut source, guild).await?;
+    meta.user_data = user_data;
+    let track = meta.track();
+
+    player.lock().await.play_overlay(input, meta, (volume as f32) / 100.0, duck);
 
     Ok(Json(track))
 }
 
-/// Pauses the provided player.
-pub async fn pause(PlayerExtractor {player, ..}: PlayerExtractor) -> impl IntoResponse {
-    player.lock().await.pause();
+/// Stops the overlays playing on top of the queue of the provided player.
+pub async fn stop_overlays(PlayerExtractor {player, ..}: PlayerExtractor) {
+    player.lock().await.stop_overlays();
+}
+
+#[derive(serde::Deserialize)]
+pub struct AutoplayQuery {
+    enabled: bool
+}
+
+/// Enables or disables autoplay, which enqueues tracks related to the last played one when
+/// the queue runs out of tracks.
+pub async fn autoplay(
+    PlayerExtractor {player, ..}: PlayerExtractor,
+    Query(query): Query<AutoplayQuery>
+) {
+    player.lock().await.autoplay = query.enabled;
+}
+
+/// Starts a sleep timer that stops the playback after some time or tracks, replacing the
+/// previous one.
+pub async fn sleep_timer(
+    PlayerExtractor {player, ..}: PlayerExtractor,
+    Json(timer): Json<SleepTimer>
+) -> Result<Json<SleepTimer>, IntoResponseError> {
+    timer.validate()
+        .map_err(|e| IntoResponseError::new(e).with_status(StatusCode::BAD_REQUEST))?;
+
+    player.lock().await.set_sleep_timer(timer, Arc::downgrade(&player));
+
+    Ok(Json(timer))
+}
+
+/// Cancels the sleep timer of the provided player.
+pub async fn cancel_sleep_timer(PlayerExtractor {player, ..}: PlayerExtractor) {
+    player.lock().await.sleep_timer = None;
+}
+
+pub(super) fn fade_duration(query: &FadeQuery) -> Result<Duration, IntoResponseError> {
+    query.duration()
+        .map_err(|e| IntoResponseError::new(e).with_status(StatusCode::BAD_REQUEST))
+}
+
+/// Pauses the provided player, fading out first if a fade duration is provided.
+pub async fn pause(
+    PlayerExtractor {player, ..}: PlayerExtractor,
+    Query(query): Query<FadeQuery>
+) -> Result<Response, IntoResponseError> {
+    let fade = fade_duration(&query)?;
+    player.lock().await.pause(fade);
 
-    Response::builder()
+    Ok(Response::builder()
         .status(StatusCode::OK)
         .body(Body::empty())
-        .unwrap()
+        .unwrap())
 }
 
-/// Resumes the provided player.
-pub async fn resume(PlayerExtractor {player, ..}: PlayerExtractor) -> impl IntoResponse {
-    player.lock().await.resume();
+/// Resumes the provided player, fading in if a fade duration is provided.
+pub async fn resume(
+    PlayerExtractor {player, ..}: PlayerExtractor,
+    Query(query): Query<FadeQuery>
+) -> Result<Response, IntoResponseError> {
+    let fade = fade_duration(&query)?;
+    player.lock().await.resume(fade).await;
 
-    Response::builder()
+    Ok(Response::builder()
         .status(StatusCode::OK)
         .body(Body::empty())
-        .unwrap()
+        .unwrap())
 }
 
 /// Changes the volume of the provided, player, take into account that going above 100 can lead
-/// to distortions in the playback.
+/// to distortions in the playback. The volume ramps to the new value if a fade duration is provided.
 pub async fn volume(
     AxumState(state): AxumState<State>,
-    Path((session, guild, volume)): Path<(Uuid, NonZeroU64, u16)>
+    Path((session, guild, volume)): Path<(Uuid, NonZeroU64, u16)>,
+    Query(query): Query<FadeQuery>
 ) -> Result<Response, IntoResponseError> {
     let PlayerExtractor { player, .. } = PlayerExtractor::from_id(session, &state, guild)?;
 
@@ -111,7 +291,8 @@ pub async fn volume(
         )
     }
 
-    player.lock().await.set_volume((volume as f32) / 100.0);
+    let fade = fade_duration(&query)?;
+    player.lock().await.set_volume((volume as f32) / 100.0, fade);
 
     Ok(Response::builder()
         .status(StatusCode::OK)
@@ -119,6 +300,8 @@ pub async fn volume(
         .unwrap())
 }
 
+/// Seeks the current track to the provided position. Positions are in the timeline of the track,
+/// so they are not affected by filters that change the playback speed.
 pub async fn seek(
     AxumState(state): AxumState<State>,
     Path((session, guild, millis)): Path<(Uuid, NonZeroU64, u64)>
@@ -140,3 +323,32 @@ pub async fn seek(
             .unwrap()
     })
 }
+
+/// Changes the loudness normalization settings of the provided player, applied to the current
+/// track right away.
+pub async fn normalization(
+    PlayerExtractor {player, ..}: PlayerExtractor,
+    Json(normalization): Json<Normalization>
+) -> Result<Json<Normalization>, IntoResponseError> {
+    normalization.validate()
+        .map_err(|e| IntoResponseError::new(e).with_status(StatusCode::BAD_REQUEST))?;
+
+    let lock = player.lock().await;
+    lock.filters.set_normalization(normalization);
+
+    Ok(Json(lock.filters.normalization()))
+}
+
+/// Changes how the provided player moves from a track to the next one in queue.
+pub async fn transition(
+    PlayerExtractor {player, ..}: PlayerExtractor,
+    Json(transition): Json<Transition>
+) -> Result<Json<Transition>, IntoResponseError> {
+    transition.validate()
+        .map_err(|e| IntoResponseError::new(e).with_status(StatusCode::BAD_REQUEST))?;
+
+    let lock = player.lock().await;
+    lock.transitions.set(transition);
+
+    Ok(Json(lock.transitions.get()))
+}
@@ -1,17 +1,38 @@
-use axum::extract::Query;
+use std::num::NonZeroU64;
+use std::time::Duration;
+use axum::extract::{Path, Query, State as AxumState};
+use axum::http::StatusCode;
 use axum::Json;
 use axum::response::{IntoResponse, Response};
 use songbird::error::TrackResult;
+use songbird::tracks::TrackHandle;
+use uuid::Uuid;
 use crate::api::error::IntoResponseError;
 use crate::api::extractors::player::PlayerExtractor;
+use crate::api::model::play::InsertOptions;
+use crate::api::model::player::{FadeQuery, QueuePage, QueuePageQuery};
+use crate::api::model::track::Track;
+use crate::api::routes::player::{fade_duration, resolve};
+use crate::api::state::State;
 use crate::api::serde::from_string::FromString;
 use crate::ext::AsyncOptionExt;
+use crate::playback::handle::PendingTrack;
 use crate::playback::metadata::TrackMetadata;
+use crate::playback::player::policy::QueuePolicy;
 
-pub async fn skip(PlayerExtractor {player, ..} : PlayerExtractor) -> Result<Response, IntoResponseError> {
-    Ok(player.lock().await
-        .queue
-        .skip()
+/// Lists the upcoming tracks of the player, a page at a time.
+pub async fn list(
+    PlayerExtractor {player, ..} : PlayerExtractor,
+    Query(query): Query<QueuePageQuery>
+) -> Result<Json<QueuePage>, IntoResponseError> {
+    query.validate()
+        .map_err(|e| IntoResponseError::new(e).with_status(StatusCode::BAD_REQUEST))?;
+
+    Ok(Json(player.lock().await.queue_page(query.offset, query.limit)))
+}
+
+fn skipped(skip: Option<TrackResult<TrackHandle>>) -> Result<Response, IntoResponseError> {
+    Ok(skip
         .transpose()?
         .map(|track| {
             Json(track.data::<TrackMetadata>().track()).into_response()
@@ -19,6 +40,164 @@ pub async fn skip(PlayerExtractor {player, ..} : PlayerExtractor) -> Result<Resp
         .unwrap_or(().into_response()))
 }
 
+pub async fn skip(
+    PlayerExtractor {player, ..} : PlayerExtractor,
+    Query(query): Query<FadeQuery>
+) -> Result<Response, IntoResponseError> {
+    let fade = fade_duration(&query)?;
+
+    skipped(player.lock().await.skip(fade))
+}
+
+/// Skips the current track and every upcoming track before the provided index, the tracks
+/// in between are discarded without being loaded.
+pub async fn skip_to(
+    AxumState(state): AxumState<State>,
+    Path((session, guild, index)): Path<(Uuid, NonZeroU64, usize)>,
+    Query(query): Query<FadeQuery>
+) -> Result<Response, IntoResponseError> {
+    let PlayerExtractor {player, ..} = PlayerExtractor::from_id(session, &state, guild)?;
+    let fade = fade_duration(&query)?;
+
+    let mut lock = player.lock().await;
+
+    if !lock.discard_before(index) {
+        return Err(IntoResponseError::new("The queue doesn't have the provided index")
+            .with_status(StatusCode::NOT_FOUND));
+    }
+
+    skipped(lock.skip(fade))
+}
+
+/// Plays the most recently played track again, the current one is paused and played
+/// next.
+pub async fn previous(
+    AxumState(state): AxumState<State>,
+    PlayerExtractor {player, guild}: PlayerExtractor
+) -> Result<Json<Track>, IntoResponseError> {
+    let Some(mut entry) = player.lock().await.history.pop() else {
+        return Err(IntoResponseError::new("The player has no previous track")
+            .with_status(StatusCode::NOT_FOUND));
+    };
+
+    let ticket = player.ticket();
+
+    let (input, mut meta) = match resolve(&state, &mut entry.source, guild).await {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            ticket.wait().await.history.restore(entry);
+            return Err(e);
+        }
+    };
+    meta.user_data = entry.track.user_data.take();
+    meta.start_time = entry.track.start_time.map(Duration::from_millis);
+    meta.end_time = entry.track.end_time.map(Duration::from_millis);
+    let track = meta.track();
+
+    ticket.wait().await.play_now(input, meta, entry.source).await;
+
+    Ok(Json(track))
+}
+
+/// Lists the tracks played by the player, the most recent one first.
+pub async fn history(PlayerExtractor {player, ..}: PlayerExtractor) -> Json<Vec<Track>> {
+    Json(player.lock().await.history.tracks().cloned().collect())
+}
+
+fn entry_not_found() -> IntoResponseError {
+    IntoResponseError::new("The queue doesn't have the provided entry")
+        .with_status(StatusCode::NOT_FOUND)
+}
+
+/// Removes the upcoming entry with the given id.
+pub async fn remove(
+    AxumState(state): AxumState<State>,
+    Path((session, guild, id)): Path<(Uuid, NonZeroU64, Uuid)>
+) -> Result<Json<Track>, IntoResponseError> {
+    let PlayerExtractor {player, ..} = PlayerExtractor::from_id(session, &state, guild)?;
+
+    player.lock().await
+        .remove(id).await
+        .map(Json)
+        .ok_or_else(entry_not_found)
+}
+
+/// Moves the upcoming entry with the given id to the provided index, the preloaded next
+/// track being index 0.
+pub async fn move_to(
+    AxumState(state): AxumState<State>,
+    Path((session, guild, id, index)): Path<(Uuid, NonZeroU64, Uuid, usize)>
+) -> Result<(), IntoResponseError> {
+    let PlayerExtractor {player, ..} = PlayerExtractor::from_id(session, &state, guild)?;
+
+    let mut lock = player.lock().await;
+
+    if !lock.queue.move_to(id, index) {
+        return Err(entry_not_found());
+    }
+
+    lock.load_next().await;
+    Ok(())
+}
+
+/// Swaps two upcoming entries.
+pub async fn swap(
+    AxumState(state): AxumState<State>,
+    Path((session, guild, first, second)): Path<(Uuid, NonZeroU64, Uuid, Uuid)>
+) -> Result<(), IntoResponseError> {
+    let PlayerExtractor {player, ..} = PlayerExtractor::from_id(session, &state, guild)?;
+
+    let mut lock = player.lock().await;
+
+    if !lock.queue.swap(first, second) {
+        return Err(entry_not_found());
+    }
+
+    lock.load_next().await;
+    Ok(())
+}
+
+/// Inserts a track at the provided index of the upcoming tracks, it is resolved once it is
+/// about to become the next track.
+pub async fn insert(
+    AxumState(state): AxumState<State>,
+    Path((session, guild, index)): Path<(Uuid, NonZeroU64, usize)>,
+    Json(options): Json<InsertOptions>
+) -> Result<Json<Track>, IntoResponseError> {
+    let PlayerExtractor {player, ..} = PlayerExtractor::from_id(session, &state, guild)?;
+    let InsertOptions { source, user_data } = options;
+
+    let mut lock = player.lock().await;
+    lock.policy.check(&lock.queue, &source, user_data.as_ref())?;
+
+    let mut pending = PendingTrack::new(source);
+    pending.track.user_data = user_data;
+
+    Ok(Json(lock.insert(index, pending).await))
+}
+
+#[derive(serde::Deserialize)]
+pub struct ShuffleQuery {
+    #[serde(default)]
+    enabled: Option<bool>
+}
+
+/// Shuffles the upcoming tracks once, or enables or disables shuffle mode if `enabled`
+/// is provided.
+pub async fn shuffle(
+    PlayerExtractor {player, ..} : PlayerExtractor,
+    Query(query): Query<ShuffleQuery>
+) {
+    let mut lock = player.lock().await;
+
+    match query.enabled {
+        Some(enabled) => lock.queue.set_shuffle(enabled),
+        None => lock.queue.shuffle()
+    }
+
+    lock.load_next().await;
+}
+
 pub async fn clear(PlayerExtractor {player, ..} : PlayerExtractor) -> impl IntoResponse
 {
     player.lock().await.queue.clear();
@@ -77,3 +256,17 @@ pub async fn repeat(
     }
     Ok(())
 }
+
+/// Changes the rules the tracks enqueued in the provided player must follow, tracks already
+/// in queue are kept.
+pub async fn policy(
+    PlayerExtractor {player, ..}: PlayerExtractor,
+    Json(policy): Json<QueuePolicy>
+) -> Result<Json<QueuePolicy>, IntoResponseError> {
+    policy.validate()
+        .map_err(|e| IntoResponseError::new(e).with_status(StatusCode::BAD_REQUEST))?;
+
+    player.lock().await.policy = policy.clone();
+
+    Ok(Json(policy))
+}
@@ -1,10 +1,13 @@
 use std::num::NonZeroU64;
+use std::sync::Arc;
 use std::time::Duration;
 use parking_lot::Mutex;
+use tokio::task::AbortHandle;
 use uuid::Uuid;
 use crate::abort::Abort;
+use crate::config::{FilterOptions, PlayerOptions};
 use crate::metrics::metrics;
-use crate::playback::Playback;
+use crate::playback::{idle, Playback};
 use crate::ptr::SharedPtr;
 use crate::source::Sources;
 
@@ -13,7 +16,9 @@ pub struct Session {
     pub id: Uuid,
     pub playback: Playback,
     pub options: Mutex<SessionOptions>,
-    pub cleanup: Mutex<Option<Abort>>
+    pub cleanup: Mutex<Option<Abort>>,
+    /// Task checking whether if the players are idle, see [`Session::start_idle_checker`].
+    idle_checker: Mutex<Option<AbortHandle>>
 }
 
 pub struct SessionOptions {
@@ -24,22 +29,40 @@ pub struct SessionOptions {
 }
 
 impl Session {
-    pub fn new(id: Uuid, user_id: NonZeroU64, sources: SharedPtr<Sources>) -> Self {
+    pub fn new(
+        id: Uuid,
+        user_id: NonZeroU64,
+        sources: SharedPtr<Sources>,
+        filter_options: Arc<FilterOptions>,
+        player_options: Arc<PlayerOptions>
+    ) -> Self {
         metrics().sessions.inc();
 
         Self {
             id,
-            playback: Playback::new(user_id, sources),
+            playback: Playback::new(user_id, sources, filter_options, player_options),
             options: Mutex::new(SessionOptions {
                 enable_resume: true,
                 timeout: Duration::from_secs(60)
             }),
-            cleanup: Mutex::new(None)
+            cleanup: Mutex::new(None),
+            idle_checker: Mutex::new(None)
         }
     }
 
+    /// Starts checking the players of the session periodically, sending `player_idle` events
+    /// for the idle ones.
+    pub fn start_idle_checker(self: &Arc<Self>) {
+        *self.idle_checker.lock() = idle::spawn_checker(self);
+    }
+
     pub async fn destroy(&self) {
         metrics().sessions.dec();
+
+        if let Some(checker) = self.idle_checker.lock().take() {
+            checker.abort();
+        }
+
         self.playback.destroy().await;
     }
 }
@@ -4,7 +4,7 @@ use dashmap::DashMap;
 use sysinfo::Pid;
 use uuid::Uuid;
 use crate::api::session::Session;
-use crate::config::Config;
+use crate::config::{Config, FilterOptions, PlayerOptions};
 use crate::metrics::MetricsTracker;
 use crate::ptr::SharedPtr;
 use crate::source::Sources;
@@ -49,6 +49,10 @@ pub struct Inner {
     pub system: SharedPtr<System>,
     /// Sources supported by nightingale.
     pub sources: SharedPtr<Sources>,
+    /// Filter options applied to new players.
+    pub filter_options: Arc<FilterOptions>,
+    /// Options of all the players.
+    pub player_options: Arc<PlayerOptions>,
 }
 
 impl Inner {
@@ -63,6 +67,8 @@ impl Inner {
             instances: Default::default(),
             system: sys,
             sources: SharedPtr::new(Sources::new(http)),
+            filter_options: Arc::new(config.filters.clone()),
+            player_options: Arc::new(config.players.clone()),
         }
     }
 
@@ -16,6 +16,7 @@ use crate::api::session::Session;
 use crate::api::state::State;
 use crate::tri;
 use crate::api::extractors::session::SessionExtractor;
+use crate::api::model::player::PlayerQuery;
 use crate::api::model::ready::Ready;
 use crate::channel::Receiver;
 
@@ -35,9 +36,18 @@ pub async fn connect(
     let id = state.generate_uuid();
 
     // Create new session.
-    state.instances.insert(id, Arc::new(Session::new(id, options.user_id, state.sources.clone())));
-
-    ws.on_upgrade(move |ws| initialize_websocket(state, ws, id, false))
+    let session = Arc::new(Session::new(
+        id,
+        options.user_id,
+        state.sources.clone(),
+        Arc::clone(&state.filter_options),
+        Arc::clone(&state.player_options)
+    ));
+
+    session.start_idle_checker();
+    state.instances.insert(id, session);
+
+    ws.on_upgrade(move |ws| initialize_websocket(state, ws, id, false, PlayerQuery::default()))
 }
 
 /// Tries to resume an existing session, if the session already has a client connected, returns
@@ -45,7 +55,8 @@ pub async fn connect(
 pub async fn resume(
     AxumState(state): AxumState<State>,
     ws: WebSocketUpgrade,
-    SessionExtractor(session): SessionExtractor
+    SessionExtractor(session): SessionExtractor,
+    Query(query): Query<PlayerQuery>
 ) -> impl IntoResponse {
     // Only one connection per session is allowed at a time, so if
     // the receiver is missing, the connection is already ongoing.
@@ -58,12 +69,19 @@ pub async fn resume(
         if let Some(abort) = session.cleanup.lock().take() {
             abort.abort(); // Tell the cleanup task to exit
         }
-        ws.on_upgrade(move |ws| initialize_websocket(state, ws, session.id, true))
+        ws.on_upgrade(move |ws| initialize_websocket(state, ws, session.id, true, query))
     }
 }
 
-/// Initializes and cleans a websocket connection.
-pub async fn initialize_websocket(state: State, websocket: WebSocket, id: Uuid, resume: bool) {
+/// Initializes and cleans a websocket connection, `query` sets how the players are sent
+/// in the ready event when resuming.
+pub async fn initialize_websocket(
+    state: State,
+    websocket: WebSocket,
+    id: Uuid,
+    resume: bool,
+    query: PlayerQuery
+) {
     let session = state.instances.get(&id).map(|s| Arc::clone(s.value())).unwrap();
 
     tokio::spawn(async move {
@@ -76,7 +94,7 @@ pub async fn initialize_websocket(state: State, websocket: WebSocket, id: Uuid,
             receiver: &mut receiver,
             session: Arc::clone(&session),
             abort: Abort::new()
-        }.run(resume).await;
+        }.run(resume, query).await;
 
         info!("Websocket connection finished");
 
@@ -133,10 +151,10 @@ struct WebSocketHandler<'a> {
 }
 
 impl WebSocketHandler<'_> {
-    #[tracing::instrument(skip(resume))]
-    async fn run(mut self, resume: bool) {
+    #[tracing::instrument(skip(resume, query))]
+    async fn run(mut self, resume: bool, query: PlayerQuery) {
         info!("Websocket connection established");
-        self.send_ready(resume).await;
+        self.send_ready(resume, query).await;
         let mut abort = self.abort.as_future();
         loop {
             tokio::select! {
@@ -173,12 +191,12 @@ impl WebSocketHandler<'_> {
         }
     }
 
-    async fn send_ready(&mut self, resume: bool) {
+    async fn send_ready(&mut self, resume: bool, query: PlayerQuery) {
         let players = if resume {
             let mut players = Vec::with_capacity(self.session.playback.players.len());
 
             for player in self.session.playback.players.iter() {
-                players.push(player.lock().await.as_json())
+                players.push(player.lock().await.as_json(query.queue))
             }
 
             Some(players)
@@ -1,7 +1,9 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use ipnet::{Ipv4Net, Ipv6Net};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use tracing::Level;
+use crate::playback::filters::FilterConfig;
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
@@ -10,7 +12,11 @@ pub struct Config {
     pub logging: LoggingOptions,
     pub metrics: MetricsOptions,
     #[serde(default)]
-    pub loki: Option<LokiOptions>
+    pub loki: Option<LokiOptions>,
+    #[serde(default)]
+    pub filters: FilterOptions,
+    #[serde(default)]
+    pub players: PlayerOptions
 }
 
 #[derive(Deserialize, Debug)]
@@ -124,3 +130,61 @@ pub struct LokiOptions {
     pub user: String,
     pub password: String
 }
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct FilterOptions {
+    /// Whether to add a limiter to new players, so volumes above 100 don't clip.
+    pub limiter: bool,
+    /// Named sets of filters that can be applied to players in a single request.
+    pub presets: BTreeMap<String, Vec<FilterConfig>>
+}
+
+impl FilterOptions {
+    /// Checks the filters of all the presets are in range.
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, filters) in self.presets.iter() {
+            for filter in filters {
+                filter.validate()
+                    .map_err(|e| format!("preset `{name}` has an invalid {} filter: {e}", filter.name()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PlayerOptions {
+    /// Seconds a player that left the voice channel is kept before being destroyed, `0` keeps
+    /// it until the client destroys it.
+    pub disconnected_ttl: u64,
+    /// Seconds a player can be idle, without a track playing or paused, before a `player_idle`
+    /// event is sent, `0` disables idle detection.
+    pub idle_timeout: u64,
+    /// What happens to players once they are idle.
+    pub idle_action: IdleAction
+}
+
+impl Default for PlayerOptions {
+    fn default() -> Self {
+        Self {
+            disconnected_ttl: 300,
+            idle_timeout: 300,
+            idle_action: IdleAction::None
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Copy, Clone, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IdleAction {
+    /// Only send the event.
+    #[default]
+    None,
+    /// Leave the voice channel keeping the player, like the leave route does.
+    Disconnect,
+    /// Destroy the player.
+    Destroy
+}
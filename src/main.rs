@@ -52,6 +52,11 @@ fn main() {
         }
     };
 
+    if let Err(e) = config.filters.validate() {
+        eprintln!("Failed to read nightingale.toml, {e}");
+        return;
+    }
+
     println!("Read nightingale.toml");
     let mut _writer_guard = None;
     let mut loki_task = None;
@@ -1,13 +1,10 @@
 use std::sync::Arc;
-use std::time::Duration;
 use songbird::{CoreEvent, Driver, Event, EventHandler, TrackEvent};
 use driver::DriverEvents;
-use periodic::PeriodicEvents;
 use track::TrackEvents;
 use crate::api::session::Session;
 use crate::playback::player::Player;
 
-mod periodic;
 mod track;
 mod driver;
 
@@ -27,11 +24,6 @@ where
 
 impl Player {
     pub async fn register_events(&mut self, session: Arc<Session>) {
-        self.driver.add_global_event(
-            Event::Periodic(Duration::from_secs(5), None),
-            PeriodicEvents::new(Arc::clone(&session))
-        );
-
         chain_events(
             &mut self.driver,
             [
@@ -33,6 +33,17 @@ impl EventHandler for TrackEvents {
         let metadata = handle.data::<TrackMetadata>();
 
         let event = match &state.playing {
+            PlayMode::Play if metadata.overlay => OutgoingEvent::OverlayStart(metadata.track()),
+            PlayMode::Stop | PlayMode::End if metadata.overlay => OutgoingEvent::OverlayEnd {
+                stopped: matches!(state.playing, PlayMode::Stop),
+                error: None,
+                track: metadata.track()
+            },
+            PlayMode::Errored(error) if metadata.overlay => OutgoingEvent::OverlayEnd {
+                stopped: false,
+                error: Some(error.to_string()),
+                track: metadata.track()
+            },
             PlayMode::Play => OutgoingEvent::TrackStart(metadata.track()),
             PlayMode::Stop => OutgoingEvent::TrackEnd {
                 stopped: true,
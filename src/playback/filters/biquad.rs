@@ -0,0 +1,147 @@
+use std::f32::consts::PI;
+use super::CHANNELS;
+
+/// Normalized coefficients of a second order IIR filter.
+#[derive(Clone, Copy, Debug)]
+pub struct Coefficients {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32
+}
+
+impl Coefficients {
+    fn normalize(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0
+        }
+    }
+
+    /// Peaking EQ centered on `frequency`, boosting or cutting by `gain_db` decibels.
+    pub fn peaking(sample_rate: u32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let a = 10f32.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * frequency / sample_rate as f32;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos = w0.cos();
+
+        Self::normalize(
+            1.0 + alpha * a,
+            -2.0 * cos,
+            1.0 - alpha * a,
+            1.0 + alpha / a,
+            -2.0 * cos,
+            1.0 - alpha / a
+        )
+    }
+
+    /// High shelf boosting or cutting frequencies above `frequency` by `gain_db` decibels.
+    pub fn high_shelf(sample_rate: u32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let a = 10f32.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * frequency / sample_rate as f32;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos = w0.cos();
+        let sqrt = 2.0 * a.sqrt() * alpha;
+
+        Self::normalize(
+            a * ((a + 1.0) + (a - 1.0) * cos + sqrt),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+            a * ((a + 1.0) + (a - 1.0) * cos - sqrt),
+            (a + 1.0) - (a - 1.0) * cos + sqrt,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos),
+            (a + 1.0) - (a - 1.0) * cos - sqrt
+        )
+    }
+
+    /// High pass filter with the given cutoff frequency.
+    pub fn high_pass(sample_rate: u32, frequency: f32, q: f32) -> Self {
+        let w0 = 2.0 * PI * frequency / sample_rate as f32;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos = w0.cos();
+
+        Self::normalize(
+            (1.0 + cos) / 2.0,
+            -(1.0 + cos),
+            (1.0 + cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha
+        )
+    }
+
+    /// Low pass filter with the given cutoff frequency.
+    pub fn low_pass(sample_rate: u32, frequency: f32, q: f32) -> Self {
+        let w0 = 2.0 * PI * frequency / sample_rate as f32;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos = w0.cos();
+
+        Self::normalize(
+            (1.0 - cos) / 2.0,
+            1.0 - cos,
+            (1.0 - cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha
+        )
+    }
+
+    /// Band pass filter centered on `frequency`, with a peak gain of 0 dB.
+    pub fn band_pass(sample_rate: u32, frequency: f32, q: f32) -> Self {
+        let w0 = 2.0 * PI * frequency / sample_rate as f32;
+        let alpha = w0.sin() / (2.0 * q);
+        let cos = w0.cos();
+
+        Self::normalize(
+            alpha,
+            0.0,
+            -alpha,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha
+        )
+    }
+}
+
+/// Second order IIR filter for interleaved stereo samples, implemented in transposed
+/// direct form II.
+pub struct Biquad {
+    coefficients: Coefficients,
+    state: [[f32; 2]; CHANNELS]
+}
+
+impl Biquad {
+    pub fn new(coefficients: Coefficients) -> Self {
+        Self {
+            coefficients,
+            state: [[0.0; 2]; CHANNELS]
+        }
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for frame in samples.chunks_exact_mut(CHANNELS) {
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                *sample = self.process_sample(channel, *sample);
+            }
+        }
+    }
+
+    /// Filters a single sample of the given channel.
+    pub fn process_sample(&mut self, channel: usize, input: f32) -> f32 {
+        let c = self.coefficients;
+        let state = &mut self.state[channel];
+        let output = c.b0 * input + state[0];
+
+        state[0] = c.b1 * input - c.a1 * output + state[1];
+        state[1] = c.b2 * input - c.a2 * output;
+
+        output
+    }
+
+    pub fn reset(&mut self) {
+        self.state = [[0.0; 2]; CHANNELS];
+    }
+}
@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use super::{AudioFilter, CHANNELS};
+
+/// Delays of the comb filters of the reverb, in frames at 44.1kHz.
+const COMB_TUNINGS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+/// Delays of the allpass filters of the reverb, in frames at 44.1kHz.
+const ALLPASS_TUNINGS: [usize; 4] = [556, 441, 341, 225];
+/// Extra delay of the right channel, makes the reverb sound wider.
+const STEREO_SPREAD: usize = 23;
+/// Sample rate the tunings were made for.
+const TUNING_RATE: f32 = 44100.0;
+/// Gain applied to the input of the reverb, the combs add up a lot of energy.
+const REVERB_INPUT_GAIN: f32 = 0.015;
+const ALLPASS_FEEDBACK: f32 = 0.5;
+
+fn default_delay() -> f32 {
+    300.0
+}
+
+fn default_feedback() -> f32 {
+    0.4
+}
+
+fn half() -> f32 {
+    0.5
+}
+
+fn default_wet() -> f32 {
+    0.33
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+/// Configuration of the [`Echo`] filter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct EchoConfig {
+    /// Time in milliseconds between every repetition.
+    #[serde(default = "default_delay")]
+    pub delay: f32,
+    /// Level of every repetition relative to the previous one, from 0 to 0.95.
+    #[serde(default = "default_feedback")]
+    pub feedback: f32,
+    /// Level of the repetitions mixed into the audio, from 0 to 1.
+    #[serde(default = "half")]
+    pub mix: f32
+}
+
+impl EchoConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if !(1.0..=2000.0).contains(&self.delay) {
+            return Err("Echo delay must be between 1 and 2000ms");
+        }
+
+        if !(0.0..=0.95).contains(&self.feedback) {
+            return Err("Echo feedback must be between 0.0 and 0.95");
+        }
+
+        if !(0.0..=1.0).contains(&self.mix) {
+            return Err("Echo mix must be between 0.0 and 1.0");
+        }
+
+        Ok(())
+    }
+}
+
+/// Repeats the audio after a delay, every repetition quieter than the previous one.
+pub struct Echo {
+    feedback: f32,
+    mix: f32,
+    buffer: Vec<[f32; CHANNELS]>,
+    pos: usize
+}
+
+impl Echo {
+    pub fn new(config: &EchoConfig, sample_rate: u32) -> Self {
+        let frames = (config.delay / 1000.0 * sample_rate as f32) as usize;
+
+        Self {
+            feedback: config.feedback,
+            mix: config.mix,
+            buffer: vec![[0.0; CHANNELS]; frames.max(1)],
+            pos: 0
+        }
+    }
+}
+
+impl AudioFilter for Echo {
+    fn name(&self) -> &'static str {
+        "echo"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        for frame in samples.chunks_exact_mut(CHANNELS) {
+            let delayed = &mut self.buffer[self.pos];
+
+            for (sample, delayed) in frame.iter_mut().zip(delayed.iter_mut()) {
+                let input = *sample;
+                *sample = input + *delayed * self.mix;
+                *delayed = input + *delayed * self.feedback;
+            }
+
+            self.pos = (self.pos + 1) % self.buffer.len();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.buffer.fill([0.0; CHANNELS]);
+        self.pos = 0;
+    }
+}
+
+/// Configuration of the [`Reverb`] filter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ReverbConfig {
+    /// Size of the simulated room, bigger rooms make the reverb last longer, from 0 to 1.
+    #[serde(default = "half")]
+    pub room_size: f32,
+    /// How fast high frequencies fade out, from 0 to 1.
+    #[serde(default = "half")]
+    pub damping: f32,
+    /// Level of the reverberated audio, from 0 to 1.
+    #[serde(default = "default_wet")]
+    pub wet: f32,
+    /// Level of the original audio, from 0 to 1.
+    #[serde(default = "one")]
+    pub dry: f32,
+    /// Stereo width of the reverberated audio, from 0 to 1.
+    #[serde(default = "one")]
+    pub width: f32
+}
+
+impl ReverbConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let values = [self.room_size, self.damping, self.wet, self.dry, self.width];
+
+        if values.iter().any(|v| !(0.0..=1.0).contains(v)) {
+            return Err("Reverb values must be between 0.0 and 1.0");
+        }
+
+        Ok(())
+    }
+}
+
+/// Lowpass feedback comb filter.
+struct Comb {
+    buffer: Vec<f32>,
+    pos: usize,
+    /// Last output, lowpassed to damp high frequencies.
+    store: f32
+}
+
+impl Comb {
+    fn new(len: usize) -> Self {
+        Self {
+            buffer: vec![0.0; len.max(1)],
+            pos: 0,
+            store: 0.0
+        }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.pos];
+        self.store = output * (1.0 - damping) + self.store * damping;
+        self.buffer[self.pos] = input + self.store * feedback;
+        self.pos = (self.pos + 1) % self.buffer.len();
+
+        output
+    }
+
+    fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.pos = 0;
+        self.store = 0.0;
+    }
+}
+
+/// Schroeder allpass filter, diffuses the output of the combs.
+struct Allpass {
+    buffer: Vec<f32>,
+    pos: usize
+}
+
+impl Allpass {
+    fn new(len: usize) -> Self {
+        Self {
+            buffer: vec![0.0; len.max(1)],
+            pos: 0
+        }
+    }
+
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.pos];
+        self.buffer[self.pos] = input + delayed * ALLPASS_FEEDBACK;
+        self.pos = (self.pos + 1) % self.buffer.len();
+
+        delayed - input
+    }
+
+    fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.pos = 0;
+    }
+}
+
+/// Reverberation of a room, based on the Freeverb algorithm: parallel comb filters
+/// followed by allpass filters in series, for every channel.
+pub struct Reverb {
+    combs: [Vec<Comb>; CHANNELS],
+    allpasses: [Vec<Allpass>; CHANNELS],
+    feedback: f32,
+    damping: f32,
+    /// Wet gain of the same channel and of the opposite channel.
+    wet: (f32, f32),
+    dry: f32
+}
+
+impl Reverb {
+    pub fn new(config: &ReverbConfig, sample_rate: u32) -> Self {
+        let scale = |frames: usize| (frames as f32 * sample_rate as f32 / TUNING_RATE) as usize;
+        let combs = |spread| COMB_TUNINGS.iter()
+            .map(|t| Comb::new(scale(t + spread)))
+            .collect::<Vec<_>>();
+        let allpasses = |spread| ALLPASS_TUNINGS.iter()
+            .map(|t| Allpass::new(scale(t + spread)))
+            .collect::<Vec<_>>();
+
+        // Scaled so the whole range of values sounds reasonable.
+        let wet = config.wet * 3.0;
+
+        Self {
+            combs: [combs(0), combs(STEREO_SPREAD)],
+            allpasses: [allpasses(0), allpasses(STEREO_SPREAD)],
+            feedback: config.room_size * 0.28 + 0.7,
+            damping: config.damping * 0.4,
+            wet: (wet * (config.width / 2.0 + 0.5), wet * ((1.0 - config.width) / 2.0)),
+            dry: config.dry
+        }
+    }
+}
+
+impl AudioFilter for Reverb {
+    fn name(&self) -> &'static str {
+        "reverb"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        for frame in samples.chunks_exact_mut(CHANNELS) {
+            let input = (frame[0] + frame[1]) * REVERB_INPUT_GAIN;
+            let mut out = [0.0; CHANNELS];
+
+            for (channel, out) in out.iter_mut().enumerate() {
+                for comb in self.combs[channel].iter_mut() {
+                    *out += comb.process(input, self.feedback, self.damping);
+                }
+
+                for allpass in self.allpasses[channel].iter_mut() {
+                    *out = allpass.process(*out);
+                }
+            }
+
+            frame[0] = out[0] * self.wet.0 + out[1] * self.wet.1 + frame[0] * self.dry;
+            frame[1] = out[1] * self.wet.0 + out[0] * self.wet.1 + frame[1] * self.dry;
+        }
+    }
+
+    fn reset(&mut self) {
+        for comb in self.combs.iter_mut().flatten() {
+            comb.reset();
+        }
+
+        for allpass in self.allpasses.iter_mut().flatten() {
+            allpass.reset();
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use super::AudioFilter;
+
+fn default_drive() -> f32 {
+    5.0
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+/// Configuration of the [`Distortion`] filter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct DistortionConfig {
+    /// How hard the audio is pushed into the clipper, from 1 to 100.
+    #[serde(default = "default_drive")]
+    pub drive: f32,
+    /// Level of the distorted audio mixed with the original one, from 0 to 1.
+    #[serde(default = "one")]
+    pub mix: f32
+}
+
+impl DistortionConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if !(1.0..=100.0).contains(&self.drive) {
+            return Err("Distortion drive must be between 1.0 and 100.0");
+        }
+
+        if !(0.0..=1.0).contains(&self.mix) {
+            return Err("Distortion mix must be between 0.0 and 1.0");
+        }
+
+        Ok(())
+    }
+}
+
+/// Soft clipping distortion, the output is normalized so full scale input stays at full scale.
+pub struct Distortion {
+    drive: f32,
+    /// Inverse of the output of the clipper at full scale.
+    normalize: f32,
+    mix: f32
+}
+
+impl Distortion {
+    pub fn new(config: &DistortionConfig) -> Self {
+        Self {
+            drive: config.drive,
+            normalize: 1.0 / config.drive.tanh(),
+            mix: config.mix
+        }
+    }
+}
+
+impl AudioFilter for Distortion {
+    fn name(&self) -> &'static str {
+        "distortion"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        for sample in samples.iter_mut() {
+            let distorted = (*sample * self.drive).tanh() * self.normalize;
+            *sample += (distorted - *sample) * self.mix;
+        }
+    }
+}
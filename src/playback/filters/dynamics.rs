@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use super::{AudioFilter, CHANNELS, db_to_gain};
+
+/// Lowest level considered by the compressor, avoids computing the logarithm of 0.
+const FLOOR: f32 = 1e-6;
+
+fn gain_to_db(gain: f32) -> f32 {
+    20.0 * gain.max(FLOOR).log10()
+}
+
+/// Smoothing coefficient of a one pole filter with the given time constant.
+fn time_coefficient(millis: f32, sample_rate: u32) -> f32 {
+    if millis <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / (millis / 1000.0 * sample_rate as f32)).exp()
+    }
+}
+
+/// Peak of a stereo frame.
+fn peak(frame: &[f32]) -> f32 {
+    frame.iter().fold(0.0, |acc, s| acc.max(s.abs()))
+}
+
+fn default_threshold() -> f32 {
+    -18.0
+}
+
+fn default_ratio() -> f32 {
+    4.0
+}
+
+fn default_attack() -> f32 {
+    10.0
+}
+
+fn default_compressor_release() -> f32 {
+    100.0
+}
+
+fn default_ceiling() -> f32 {
+    -1.0
+}
+
+fn default_limiter_release() -> f32 {
+    50.0
+}
+
+/// Configuration of the [`Compressor`] filter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct CompressorConfig {
+    /// Level in dBFS above which the audio is compressed.
+    #[serde(default = "default_threshold")]
+    pub threshold: f32,
+    /// Compression ratio, a ratio of 4 turns 4dB above the threshold into 1dB.
+    #[serde(default = "default_ratio")]
+    pub ratio: f32,
+    /// Time in milliseconds the compressor takes to react to louder audio.
+    #[serde(default = "default_attack")]
+    pub attack: f32,
+    /// Time in milliseconds the compressor takes to recover after the audio gets quieter.
+    #[serde(default = "default_compressor_release")]
+    pub release: f32,
+    /// Gain in dB applied after compressing.
+    #[serde(default)]
+    pub makeup: f32
+}
+
+impl Default for CompressorConfig {
+    fn default() -> Self {
+        Self {
+            threshold: default_threshold(),
+            ratio: default_ratio(),
+            attack: default_attack(),
+            release: default_compressor_release(),
+            makeup: 0.0
+        }
+    }
+}
+
+impl CompressorConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if !(-60.0..=0.0).contains(&self.threshold) {
+            return Err("Compressor threshold must be between -60 and 0 dB");
+        }
+
+        if !(1.0..=20.0).contains(&self.ratio) {
+            return Err("Compressor ratio must be between 1 and 20");
+        }
+
+        if !(0.0..=1000.0).contains(&self.attack) || !(0.0..=5000.0).contains(&self.release) {
+            return Err("Compressor attack must be between 0 and 1000ms, and release between 0 and 5000ms");
+        }
+
+        if !(0.0..=24.0).contains(&self.makeup) {
+            return Err("Compressor makeup gain must be between 0 and 24 dB");
+        }
+
+        Ok(())
+    }
+}
+
+/// Feed-forward compressor, with its detector linked across channels.
+pub struct Compressor {
+    threshold: f32,
+    slope: f32,
+    makeup: f32,
+    attack: f32,
+    release: f32,
+    envelope: f32
+}
+
+impl Compressor {
+    pub fn new(config: &CompressorConfig, sample_rate: u32) -> Self {
+        Self {
+            threshold: config.threshold,
+            slope: 1.0 / config.ratio - 1.0,
+            makeup: config.makeup,
+            attack: time_coefficient(config.attack, sample_rate),
+            release: time_coefficient(config.release, sample_rate),
+            envelope: 0.0
+        }
+    }
+}
+
+impl AudioFilter for Compressor {
+    fn name(&self) -> &'static str {
+        "compressor"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        for frame in samples.chunks_exact_mut(CHANNELS) {
+            let level = peak(frame);
+            let coefficient = if level > self.envelope { self.attack } else { self.release };
+            self.envelope = level + coefficient * (self.envelope - level);
+
+            let over = gain_to_db(self.envelope) - self.threshold;
+            let reduction = if over > 0.0 { over * self.slope } else { 0.0 };
+            let gain = db_to_gain(reduction + self.makeup);
+
+            for sample in frame.iter_mut() {
+                *sample *= gain;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+}
+
+/// Configuration of the [`Limiter`] filter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct LimiterConfig {
+    /// Maximum level in dBFS of the output.
+    #[serde(default = "default_ceiling")]
+    pub ceiling: f32,
+    /// Time in milliseconds the limiter takes to recover after a peak.
+    #[serde(default = "default_limiter_release")]
+    pub release: f32
+}
+
+impl Default for LimiterConfig {
+    fn default() -> Self {
+        Self {
+            ceiling: default_ceiling(),
+            release: default_limiter_release()
+        }
+    }
+}
+
+impl LimiterConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if !(-24.0..=0.0).contains(&self.ceiling) {
+            return Err("Limiter ceiling must be between -24 and 0 dB");
+        }
+
+        if !(1.0..=5000.0).contains(&self.release) {
+            return Err("Limiter release must be between 1 and 5000ms");
+        }
+
+        Ok(())
+    }
+}
+
+/// Peak limiter, reduces the gain instantly when a frame would go above the ceiling, so
+/// the output never clips, and recovers smoothly afterwards.
+pub struct Limiter {
+    ceiling: f32,
+    release: f32,
+    gain: f32
+}
+
+impl Limiter {
+    pub fn new(config: &LimiterConfig, sample_rate: u32) -> Self {
+        Self {
+            ceiling: db_to_gain(config.ceiling),
+            release: time_coefficient(config.release, sample_rate),
+            gain: 1.0
+        }
+    }
+}
+
+impl AudioFilter for Limiter {
+    fn name(&self) -> &'static str {
+        "limiter"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        for frame in samples.chunks_exact_mut(CHANNELS) {
+            let level = peak(frame);
+            let target = if level > self.ceiling { self.ceiling / level } else { 1.0 };
+
+            self.gain = if target < self.gain {
+                target
+            } else {
+                target + self.release * (self.gain - target)
+            };
+
+            for sample in frame.iter_mut() {
+                *sample *= self.gain;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.gain = 1.0;
+    }
+}
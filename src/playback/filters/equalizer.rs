@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use super::AudioFilter;
+use super::biquad::{Biquad, Coefficients};
+
+/// Center frequencies of the equalizer bands.
+const BANDS: [f32; 15] = [
+    25.0, 40.0, 63.0, 100.0, 160.0, 250.0, 400.0, 630.0,
+    1000.0, 1600.0, 2500.0, 4000.0, 6300.0, 10000.0, 16000.0
+];
+/// Quality factor of every band, wide enough for adjacent bands to overlap smoothly.
+const BAND_Q: f32 = 1.4;
+/// Lowest attenuation applied to a band, used instead of completely muting it.
+const MIN_DB: f32 = -40.0;
+
+/// A single band of the equalizer.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct EqualizerBand {
+    /// Band index, from 0 to 14.
+    pub band: u8,
+    /// Gain multiplier of the band, `-0.25` mutes the band, `0.0` leaves it untouched
+    /// and `0.25` doubles it.
+    pub gain: f32
+}
+
+/// Configuration of the [`Equalizer`] filter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct EqualizerConfig {
+    /// Bands to modify, bands not provided are left untouched.
+    pub bands: Vec<EqualizerBand>
+}
+
+impl EqualizerConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        for band in self.bands.iter() {
+            if band.band as usize >= BANDS.len() {
+                return Err("Equalizer bands must be between 0 and 14");
+            }
+
+            if !(-0.25..=1.0).contains(&band.gain) {
+                return Err("Equalizer gains must be between -0.25 and 1.0");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Equalizer made of peaking filters.
+pub struct Equalizer {
+    bands: Vec<Biquad>
+}
+
+impl Equalizer {
+    pub fn new(config: &EqualizerConfig, sample_rate: u32) -> Self {
+        let nyquist = sample_rate as f32 / 2.0;
+
+        let bands = config.bands.iter()
+            .filter(|b| b.gain != 0.0 && BANDS[b.band as usize] < nyquist)
+            .map(|b| {
+                let multiplier = 1.0 + b.gain * 4.0;
+                let db = if multiplier > 0.0 {
+                    (20.0 * multiplier.log10()).max(MIN_DB)
+                } else {
+                    MIN_DB
+                };
+
+                Biquad::new(Coefficients::peaking(sample_rate, BANDS[b.band as usize], BAND_Q, db))
+            })
+            .collect();
+
+        Self {
+            bands
+        }
+    }
+}
+
+impl AudioFilter for Equalizer {
+    fn name(&self) -> &'static str {
+        "equalizer"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        for band in self.bands.iter_mut() {
+            band.process(samples);
+        }
+    }
+
+    fn reset(&mut self) {
+        for band in self.bands.iter_mut() {
+            band.reset();
+        }
+    }
+}
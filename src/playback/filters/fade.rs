@@ -0,0 +1,133 @@
+use std::time::Duration;
+use parking_lot::Mutex;
+use super::CHANNELS;
+
+/// Gain of a single track that can be faded smoothly, shared between the player and the
+/// source of the track.
+#[derive(Default)]
+pub struct Fade {
+    /// Last requested fade, taken by the source on its next read.
+    pending: Mutex<Option<FadeRequest>>
+}
+
+#[derive(Clone, Copy)]
+struct FadeRequest {
+    from: Option<f32>,
+    to: f32,
+    duration: Duration
+}
+
+impl Fade {
+    /// Fades the track from its current gain to the provided one over `duration`, a zero
+    /// duration changes the gain instantly.
+    pub fn fade_to(&self, gain: f32, duration: Duration) {
+        *self.pending.lock() = Some(FadeRequest {
+            from: None,
+            to: gain,
+            duration
+        });
+    }
+
+    /// Fades the track in from silence over `duration`.
+    pub fn fade_in(&self, duration: Duration) {
+        *self.pending.lock() = Some(FadeRequest {
+            from: Some(0.0),
+            to: 1.0,
+            duration
+        });
+    }
+
+    fn take(&self) -> Option<FadeRequest> {
+        self.pending.lock().take()
+    }
+}
+
+/// Applies the gain requested through a [`Fade`] to the samples of a source.
+pub struct Fader {
+    ramp: Ramp
+}
+
+impl Fader {
+    pub fn new() -> Self {
+        Self {
+            ramp: Ramp::new(1.0)
+        }
+    }
+
+    /// Picks up the last fade requested, if any.
+    pub fn update(&mut self, fade: &Fade, sample_rate: u32) {
+        let Some(request) = fade.take() else {
+            return;
+        };
+
+        if let Some(from) = request.from {
+            self.ramp.gain = from;
+        }
+
+        self.ramp.ramp_to(request.to, request.duration, sample_rate);
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        self.ramp.process(samples);
+    }
+}
+
+/// Gain that moves linearly towards a target, one frame at a time.
+pub(super) struct Ramp {
+    gain: f32,
+    target: f32,
+    /// Gain change per frame.
+    step: f32
+}
+
+impl Ramp {
+    pub fn new(gain: f32) -> Self {
+        Self {
+            gain,
+            target: gain,
+            step: 0.0
+        }
+    }
+
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    /// Starts moving towards `target`, reaching it after `duration`.
+    pub fn ramp_to(&mut self, target: f32, duration: Duration, sample_rate: u32) {
+        let frames = duration.as_secs_f32() * sample_rate as f32;
+
+        self.target = target;
+        if frames < 1.0 {
+            self.gain = target;
+            self.step = 0.0;
+        } else {
+            self.step = (target - self.gain) / frames;
+        }
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        if self.step == 0.0 {
+            if self.gain != 1.0 {
+                for sample in samples.iter_mut() {
+                    *sample *= self.gain;
+                }
+            }
+
+            return;
+        }
+
+        for frame in samples.chunks_exact_mut(CHANNELS) {
+            self.gain += self.step;
+
+            if (self.step > 0.0 && self.gain >= self.target) || (self.step < 0.0 && self.gain <= self.target) {
+                self.gain = self.target;
+                self.step = 0.0;
+            }
+
+            for sample in frame.iter_mut() {
+                *sample *= self.gain;
+            }
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use super::AudioFilter;
+use super::biquad::{Biquad, Coefficients};
+
+/// Quality factor of the filter, the flattest response without any resonance.
+const Q: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+fn default_cutoff() -> f32 {
+    1000.0
+}
+
+/// Configuration of the [`LowPass`] filter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct LowPassConfig {
+    /// Frequency in Hz above which the audio is attenuated.
+    #[serde(default = "default_cutoff")]
+    pub cutoff: f32
+}
+
+impl LowPassConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if !(20.0..=20000.0).contains(&self.cutoff) {
+            return Err("Low pass cutoff must be between 20 and 20000 Hz");
+        }
+
+        Ok(())
+    }
+}
+
+/// Attenuates the frequencies above the cutoff, muffling the audio.
+pub struct LowPass {
+    filter: Biquad
+}
+
+impl LowPass {
+    pub fn new(config: &LowPassConfig, sample_rate: u32) -> Self {
+        let cutoff = config.cutoff.min(sample_rate as f32 * 0.45);
+
+        Self {
+            filter: Biquad::new(Coefficients::low_pass(sample_rate, cutoff, Q))
+        }
+    }
+}
+
+impl AudioFilter for LowPass {
+    fn name(&self) -> &'static str {
+        "low_pass"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        self.filter.process(samples);
+    }
+
+    fn reset(&mut self) {
+        self.filter.reset();
+    }
+}
@@ -1,5 +1,56 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::time::Duration;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use delay::{Echo, EchoConfig, Reverb, ReverbConfig};
+use distortion::{Distortion, DistortionConfig};
+use dynamics::{Compressor, CompressorConfig, Limiter, LimiterConfig};
+use equalizer::{Equalizer, EqualizerConfig};
+use fade::Ramp;
+use low_pass::{LowPass, LowPassConfig};
+use modulation::{Tremolo, TremoloConfig, Vibrato, VibratoConfig};
+use normalization::{Normalization, NormalizationMode, Normalizer};
+use spatial::{ChannelMix, ChannelMixConfig, Karaoke, KaraokeConfig, Rotation, RotationConfig};
+use timescale::{Timescale, TimescaleConfig};
+use volume::{Volume, VolumeConfig};
+
+pub mod source;
+pub mod volume;
+pub mod equalizer;
+pub mod timescale;
+pub mod spatial;
+pub mod modulation;
+pub mod low_pass;
+pub mod distortion;
+pub mod delay;
+pub mod dynamics;
+pub mod normalization;
+pub mod fade;
+mod biquad;
+
+/// Number of channels filters work with, every source is converted to stereo before
+/// going through the chain.
+pub const CHANNELS: usize = 2;
+
+/// Filters with this order or higher are applied after the player volume.
+const DYNAMICS_ORDER: u8 = 16;
+
+/// Converts decibels into a linear gain.
+pub fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// A chain of filters applied in order to the decoded samples of a track.
 pub struct FilterChain {
-    filters: Vec<Box<dyn AudioFilter>>
+    filters: Vec<ChainedFilter>
+}
+
+/// A filter of a [`FilterChain`] with the configuration it was built from, filters
+/// without one are managed by the player, like its volume.
+struct ChainedFilter {
+    config: Option<FilterConfig>,
+    filter: Box<dyn AudioFilter>
 }
 
 impl FilterChain {
@@ -9,15 +60,512 @@ impl FilterChain {
         }
     }
 
-    pub fn add_filter(&mut self, filter: Box<dyn AudioFilter>) {
-        self.filters.push(filter);
+    fn add_filter(&mut self, config: Option<FilterConfig>, filter: Box<dyn AudioFilter>) {
+        self.filters.push(ChainedFilter {
+            config,
+            filter
+        });
+    }
+
+    /// Takes the filter built from the provided configuration out of the chain, if present.
+    fn take(&mut self, config: Option<&FilterConfig>) -> Option<ChainedFilter> {
+        let index = self.filters.iter().position(|f| f.config.as_ref() == config)?;
+        Some(self.filters.remove(index))
+    }
+
+    /// Moves the filter built from `config` out of `previous` into this chain, building it
+    /// if `previous` doesn't have it. Returns whether if the filter was built.
+    fn reuse_or_build(&mut self, previous: &mut FilterChain, config: &FilterConfig, sample_rate: u32) -> bool {
+        match previous.take(Some(config)) {
+            Some(reused) => {
+                self.filters.push(reused);
+                false
+            },
+            None => {
+                self.add_filter(Some(config.clone()), config.build(sample_rate));
+                true
+            }
+        }
+    }
+
+    /// Runs the provided interleaved stereo samples through all the filters of the chain.
+    pub fn process(&mut self, samples: &mut Vec<f32>) {
+        for chained in self.filters.iter_mut() {
+            chained.filter.process(samples);
+        }
     }
 
-    pub fn remove_filter(&mut self, filter: Box<dyn AudioFilter>) {
-        self.filters.retain(|f| f.name() != filter.name());
+    /// Resets the internal state of all the filters of the chain.
+    pub fn reset(&mut self) {
+        for chained in self.filters.iter_mut() {
+            chained.filter.reset();
+        }
     }
 }
 
+/// A filter that processes decoded audio.
+///
+/// Filters receive interleaved stereo `f32` samples, and are allowed to change the
+/// number of samples they output, as long as they output whole frames.
 pub trait AudioFilter: Send + Sync + 'static {
     fn name(&self) -> &'static str;
-}
\ No newline at end of file
+
+    fn process(&mut self, samples: &mut Vec<f32>);
+
+    /// Clears any state kept between calls to [`process`](Self::process).
+    fn reset(&mut self) {}
+}
+
+/// Serializable configuration of a filter, used to build [`AudioFilter`]s for every
+/// track of a player.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub enum FilterConfig {
+    Equalizer(EqualizerConfig),
+    Karaoke(KaraokeConfig),
+    Timescale(TimescaleConfig),
+    Tremolo(TremoloConfig),
+    Vibrato(VibratoConfig),
+    Rotation(RotationConfig),
+    Distortion(DistortionConfig),
+    ChannelMix(ChannelMixConfig),
+    LowPass(LowPassConfig),
+    Echo(EchoConfig),
+    Reverb(ReverbConfig),
+    Volume(VolumeConfig),
+    Compressor(CompressorConfig),
+    Limiter(LimiterConfig)
+}
+
+impl FilterConfig {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Equalizer(_) => "equalizer",
+            Self::Karaoke(_) => "karaoke",
+            Self::Timescale(_) => "timescale",
+            Self::Tremolo(_) => "tremolo",
+            Self::Vibrato(_) => "vibrato",
+            Self::Rotation(_) => "rotation",
+            Self::Distortion(_) => "distortion",
+            Self::ChannelMix(_) => "channel_mix",
+            Self::LowPass(_) => "low_pass",
+            Self::Echo(_) => "echo",
+            Self::Reverb(_) => "reverb",
+            Self::Volume(_) => "volume",
+            Self::Compressor(_) => "compressor",
+            Self::Limiter(_) => "limiter"
+        }
+    }
+
+    /// Position of the filter inside a [`FilterChain`], lower values are applied first.
+    fn order(&self) -> u8 {
+        match self {
+            Self::Equalizer(_) => 0,
+            Self::Karaoke(_) => 1,
+            Self::Timescale(_) => 2,
+            Self::Tremolo(_) => 3,
+            Self::Vibrato(_) => 4,
+            Self::Rotation(_) => 5,
+            Self::Distortion(_) => 6,
+            Self::ChannelMix(_) => 7,
+            Self::LowPass(_) => 8,
+            Self::Echo(_) => 9,
+            Self::Reverb(_) => 10,
+            Self::Volume(_) => 11,
+            Self::Compressor(_) => DYNAMICS_ORDER,
+            Self::Limiter(_) => DYNAMICS_ORDER + 1
+        }
+    }
+
+    /// Checks the configuration values are in range.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        match self {
+            Self::Equalizer(c) => c.validate(),
+            Self::Karaoke(c) => c.validate(),
+            Self::Timescale(c) => c.validate(),
+            Self::Tremolo(c) => c.validate(),
+            Self::Vibrato(c) => c.validate(),
+            Self::Rotation(c) => c.validate(),
+            Self::Distortion(c) => c.validate(),
+            Self::ChannelMix(c) => c.validate(),
+            Self::LowPass(c) => c.validate(),
+            Self::Echo(c) => c.validate(),
+            Self::Reverb(c) => c.validate(),
+            Self::Volume(c) => c.validate(),
+            Self::Compressor(c) => c.validate(),
+            Self::Limiter(c) => c.validate()
+        }
+    }
+
+    /// Creates the filter described by this configuration.
+    pub fn build(&self, sample_rate: u32) -> Box<dyn AudioFilter> {
+        match self {
+            Self::Equalizer(c) => Box::new(Equalizer::new(c, sample_rate)),
+            Self::Karaoke(c) => Box::new(Karaoke::new(c, sample_rate)),
+            Self::Timescale(c) => Box::new(Timescale::new(c, sample_rate)),
+            Self::Tremolo(c) => Box::new(Tremolo::new(c, sample_rate)),
+            Self::Vibrato(c) => Box::new(Vibrato::new(c, sample_rate)),
+            Self::Rotation(c) => Box::new(Rotation::new(c, sample_rate)),
+            Self::Distortion(c) => Box::new(Distortion::new(c)),
+            Self::ChannelMix(c) => Box::new(ChannelMix::new(c)),
+            Self::LowPass(c) => Box::new(LowPass::new(c, sample_rate)),
+            Self::Echo(c) => Box::new(Echo::new(c, sample_rate)),
+            Self::Reverb(c) => Box::new(Reverb::new(c, sample_rate)),
+            Self::Volume(c) => Box::new(Volume::new(c)),
+            Self::Compressor(c) => Box::new(Compressor::new(c, sample_rate)),
+            Self::Limiter(c) => Box::new(Limiter::new(c, sample_rate))
+        }
+    }
+}
+
+/// Filters of a player, shared with the sources of all its tracks so changes
+/// apply to the currently playing track too.
+pub struct Filters {
+    configs: RwLock<Vec<FilterConfig>>,
+    /// Filters set by the server configuration, kept when the filters are replaced or cleared.
+    defaults: RwLock<Vec<FilterConfig>>,
+    /// Incremented on every change, so sources know when to rebuild their chain.
+    version: AtomicU64,
+    volume: Arc<SharedVolume>,
+    normalization: RwLock<Normalization>
+}
+
+impl Filters {
+    pub fn new() -> Self {
+        Self {
+            configs: RwLock::new(Vec::new()),
+            defaults: RwLock::new(Vec::new()),
+            version: AtomicU64::new(0),
+            volume: Arc::new(SharedVolume {
+                volume: SharedGain::new(),
+                duck: SharedGain::new()
+            }),
+            normalization: RwLock::new(Normalization::default())
+        }
+    }
+
+    pub fn normalization(&self) -> Normalization {
+        self.normalization.read().clone()
+    }
+
+    pub fn set_normalization(&self, normalization: Normalization) {
+        *self.normalization.write() = normalization;
+        self.bump();
+    }
+
+    /// Builds the normalizer of a track if normalization is enabled, `loudness` is the loudness
+    /// of the track in LUFS, if known.
+    pub fn build_normalizer(&self, loudness: Option<f32>, sample_rate: u32) -> Option<Normalizer> {
+        let normalization = self.normalization.read();
+
+        match normalization.mode {
+            NormalizationMode::Off => None,
+            NormalizationMode::Track => Some(Normalizer::new(&normalization, loudness, sample_rate))
+        }
+    }
+
+    /// Applies the current normalization settings to the normalizer of a track, keeping the
+    /// existing one so the loudness measured so far isn't lost.
+    pub fn update_normalizer(&self, normalizer: &mut Option<Normalizer>, loudness: Option<f32>, sample_rate: u32) {
+        let normalization = self.normalization.read();
+
+        match (normalization.mode, normalizer.as_mut()) {
+            (NormalizationMode::Off, _) => *normalizer = None,
+            (NormalizationMode::Track, Some(existing)) => existing.set_target(normalization.target),
+            (NormalizationMode::Track, None) => {
+                *normalizer = Some(Normalizer::new(&normalization, loudness, sample_rate));
+            }
+        }
+    }
+
+    /// Sets the volume of the player, applied before the compressor and limiter filters
+    /// so loud volumes can be tamed by them. The volume ramps to the new value over `ramp`.
+    pub fn set_volume(&self, volume: f32, ramp: Duration) {
+        self.volume.volume.set(volume, ramp);
+    }
+
+    /// Lowers the volume of the player to the provided fraction of it, ramping to it over
+    /// `ramp`. The volume can still be changed while ducked.
+    pub fn set_duck(&self, duck: f32, ramp: Duration) {
+        self.volume.duck.set(duck, ramp);
+    }
+
+    pub fn get(&self) -> Vec<FilterConfig> {
+        self.configs.read().clone()
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    /// Sets the provided filter, replacing the existing one of the same kind if present.
+    pub fn set(&self, filter: FilterConfig) {
+        self.merge([filter]);
+    }
+
+    /// Sets all the provided filters at once, replacing the existing ones of the same kind.
+    pub fn merge(&self, filters: impl IntoIterator<Item = FilterConfig>) {
+        let mut configs = self.configs.write();
+
+        for filter in filters {
+            configs.retain(|f| f.name() != filter.name());
+            configs.push(filter);
+        }
+
+        configs.sort_by_key(FilterConfig::order);
+        self.bump();
+    }
+
+    /// Sets the filters of the server configuration, which are added back when the filters
+    /// are replaced or cleared unless replaced by ones of the same kind.
+    pub fn set_defaults(&self, defaults: Vec<FilterConfig>) {
+        self.merge(defaults.iter().cloned());
+        *self.defaults.write() = defaults;
+    }
+
+    /// Replaces all the filters with the provided ones, if a kind of filter is repeated,
+    /// the last one is kept. Default filters of kinds not provided are kept.
+    pub fn replace(&self, filters: Vec<FilterConfig>) {
+        let mut configs = Vec::with_capacity(filters.len());
+
+        for filter in filters {
+            configs.retain(|f: &FilterConfig| f.name() != filter.name());
+            configs.push(filter);
+        }
+
+        for default in self.defaults.read().iter() {
+            if configs.iter().all(|f| f.name() != default.name()) {
+                configs.push(default.clone());
+            }
+        }
+
+        configs.sort_by_key(FilterConfig::order);
+        *self.configs.write() = configs;
+        self.bump();
+    }
+
+    /// Removes the filter with the given name, returns whether if it was present.
+    pub fn remove(&self, name: &str) -> bool {
+        let mut configs = self.configs.write();
+        let len = configs.len();
+        configs.retain(|f| f.name() != name);
+
+        let removed = configs.len() != len;
+        if removed {
+            self.bump();
+        }
+
+        removed
+    }
+
+    /// Removes all the filters except the default ones.
+    pub fn clear(&self) {
+        *self.configs.write() = self.defaults.read().clone();
+        self.bump();
+    }
+
+    /// Builds a new chain with the current filters, the player volume is inserted right
+    /// before the dynamics filters.
+    pub fn build_chain(&self, sample_rate: u32) -> FilterChain {
+        self.rebuild_chain(FilterChain::new(), sample_rate).0
+    }
+
+    /// Builds a chain with the current filters reusing the ones of `previous` whose
+    /// configuration didn't change, so they keep their state like the buffers of echo and
+    /// reverb. Also returns whether if any filter was built or dropped.
+    pub fn rebuild_chain(&self, mut previous: FilterChain, sample_rate: u32) -> (FilterChain, bool) {
+        let mut chain = FilterChain::new();
+        let mut changed = false;
+        let configs = self.configs.read();
+        let (pre, post): (Vec<_>, Vec<_>) = configs.iter()
+            .partition(|c| c.order() < DYNAMICS_ORDER);
+
+        for config in pre {
+            changed |= chain.reuse_or_build(&mut previous, config, sample_rate);
+        }
+
+        match previous.take(None) {
+            Some(volume) => chain.filters.push(volume),
+            None => chain.add_filter(None, Box::new(PlayerVolume::new(Arc::clone(&self.volume), sample_rate)))
+        }
+
+        for config in post {
+            changed |= chain.reuse_or_build(&mut previous, config, sample_rate);
+        }
+
+        (chain, changed || !previous.filters.is_empty())
+    }
+
+    fn bump(&self) {
+        self.version.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// Volume of a player, shared with the chains of all its tracks.
+struct SharedVolume {
+    volume: SharedGain,
+    /// Fraction of the volume kept while overlays duck the music.
+    duck: SharedGain
+}
+
+/// A gain the chains ramp to when it changes, each gain ramps on its own so changing one
+/// doesn't affect a ramp of the other.
+struct SharedGain {
+    /// Bits of the gain as an `f32`.
+    gain: AtomicU32,
+    /// Time in milliseconds the chains take to reach the gain.
+    ramp: AtomicU64
+}
+
+impl SharedGain {
+    fn new() -> Self {
+        Self {
+            gain: AtomicU32::new(1f32.to_bits()),
+            ramp: AtomicU64::new(0)
+        }
+    }
+
+    fn get(&self) -> f32 {
+        f32::from_bits(self.gain.load(Ordering::Acquire))
+    }
+
+    fn set(&self, gain: f32, ramp: Duration) {
+        self.ramp.store(ramp.as_millis() as u64, Ordering::Relaxed);
+        self.gain.store(gain.to_bits(), Ordering::Release);
+    }
+
+    /// Ramps `ramp` to the gain if it changed.
+    fn update(&self, ramp: &mut Ramp, sample_rate: u32) {
+        let gain = self.get();
+
+        if gain != ramp.target() {
+            let duration = Duration::from_millis(self.ramp.load(Ordering::Relaxed));
+            ramp.ramp_to(gain, duration, sample_rate);
+        }
+    }
+}
+
+/// Applies the volume of the player, ramping to it when it changes.
+struct PlayerVolume {
+    shared: Arc<SharedVolume>,
+    sample_rate: u32,
+    volume: Ramp,
+    duck: Ramp
+}
+
+impl PlayerVolume {
+    fn new(shared: Arc<SharedVolume>, sample_rate: u32) -> Self {
+        Self {
+            volume: Ramp::new(shared.volume.get()),
+            duck: Ramp::new(shared.duck.get()),
+            shared,
+            sample_rate
+        }
+    }
+}
+
+impl AudioFilter for PlayerVolume {
+    fn name(&self) -> &'static str {
+        "player_volume"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        self.shared.volume.update(&mut self.volume, self.sample_rate);
+        self.shared.duck.update(&mut self.duck, self.sample_rate);
+
+        self.volume.process(samples);
+        self.duck.process(samples);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use super::*;
+
+    fn config(kind: &str, data: Value) -> FilterConfig {
+        serde_json::from_value(json!({ "type": kind, "data": data })).unwrap()
+    }
+
+    fn names(filters: &Filters) -> Vec<&'static str> {
+        filters.get().iter().map(FilterConfig::name).collect()
+    }
+
+    #[test]
+    fn spatial_and_modulation_defaults_are_valid() {
+        for kind in ["rotation", "channel_mix", "karaoke", "tremolo", "vibrato", "low_pass"] {
+            assert!(config(kind, json!({})).validate().is_ok(), "{kind}");
+        }
+    }
+
+    #[test]
+    fn spatial_and_modulation_ranges_are_validated() {
+        assert!(config("rotation", json!({ "rotation_hz": 5.5 })).validate().is_err());
+        assert!(config("channel_mix", json!({ "left_to_right": 1.5 })).validate().is_err());
+        assert!(config("karaoke", json!({ "level": -0.5 })).validate().is_err());
+        assert!(config("karaoke", json!({ "filter_band": 10.0 })).validate().is_err());
+        assert!(config("tremolo", json!({ "frequency": 0.0 })).validate().is_err());
+        assert!(config("tremolo", json!({ "depth": 1.5 })).validate().is_err());
+        assert!(config("vibrato", json!({ "frequency": 15.0 })).validate().is_err());
+        assert!(config("low_pass", json!({ "cutoff": 25000.0 })).validate().is_err());
+    }
+
+    #[test]
+    fn time_domain_defaults_are_valid() {
+        for kind in ["echo", "reverb", "distortion"] {
+            assert!(config(kind, json!({})).validate().is_ok(), "{kind}");
+        }
+    }
+
+    #[test]
+    fn time_domain_ranges_are_validated() {
+        assert!(config("echo", json!({ "delay": 0.0 })).validate().is_err());
+        assert!(config("echo", json!({ "feedback": 1.0 })).validate().is_err());
+        assert!(config("echo", json!({ "mix": 1.5 })).validate().is_err());
+        assert!(config("reverb", json!({ "room_size": 1.5 })).validate().is_err());
+        assert!(config("reverb", json!({ "wet": -0.1 })).validate().is_err());
+        assert!(config("distortion", json!({ "drive": 0.5 })).validate().is_err());
+        assert!(config("distortion", json!({ "mix": 2.0 })).validate().is_err());
+    }
+
+    #[test]
+    fn time_domain_filters_are_kept_in_order() {
+        let filters = Filters::new();
+
+        filters.merge([
+            config("volume", json!({ "volume": 1.0 })),
+            config("reverb", json!({})),
+            config("low_pass", json!({})),
+            config("echo", json!({})),
+            config("distortion", json!({}))
+        ]);
+
+        assert_eq!(names(&filters), ["distortion", "low_pass", "echo", "reverb", "volume"]);
+    }
+
+    #[test]
+    fn filters_are_kept_in_order() {
+        let filters = Filters::new();
+
+        filters.merge([
+            config("low_pass", json!({})),
+            config("limiter", json!({})),
+            config("rotation", json!({})),
+            config("karaoke", json!({})),
+            config("volume", json!({ "volume": 1.0 })),
+            config("tremolo", json!({}))
+        ]);
+
+        assert_eq!(names(&filters), ["karaoke", "tremolo", "rotation", "low_pass", "volume", "limiter"]);
+    }
+
+    #[test]
+    fn setting_a_filter_replaces_the_same_kind() {
+        let filters = Filters::new();
+        filters.set(config("rotation", json!({ "rotation_hz": 1.0 })));
+        filters.set(config("rotation", json!({ "rotation_hz": 2.0 })));
+
+        assert_eq!(filters.get(), [config("rotation", json!({ "rotation_hz": 2.0 }))]);
+    }
+}
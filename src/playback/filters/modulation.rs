@@ -0,0 +1,178 @@
+use std::f32::consts::TAU;
+use serde::{Deserialize, Serialize};
+use super::{AudioFilter, CHANNELS};
+
+/// Longest delay used by the vibrato at full depth, in seconds.
+const VIBRATO_MAX_DELAY: f32 = 0.002;
+
+fn default_frequency() -> f32 {
+    2.0
+}
+
+fn default_depth() -> f32 {
+    0.5
+}
+
+/// Low frequency oscillator driving the modulation filters, outputs values from 0 to 1.
+struct Lfo {
+    step: f32,
+    phase: f32
+}
+
+impl Lfo {
+    fn new(frequency: f32, sample_rate: u32) -> Self {
+        Self {
+            step: TAU * frequency / sample_rate as f32,
+            phase: 0.0
+        }
+    }
+
+    fn next(&mut self) -> f32 {
+        let value = (1.0 - self.phase.cos()) / 2.0;
+        self.phase = (self.phase + self.step) % TAU;
+        value
+    }
+}
+
+/// Configuration of the [`Tremolo`] filter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct TremoloConfig {
+    /// Oscillations per second.
+    #[serde(default = "default_frequency")]
+    pub frequency: f32,
+    /// How much the volume drops at the bottom of every oscillation, from 0 to 1.
+    #[serde(default = "default_depth")]
+    pub depth: f32
+}
+
+impl TremoloConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.frequency <= 0.0 || self.frequency > 20.0 {
+            return Err("Tremolo frequency must be greater than 0 and up to 20 Hz");
+        }
+
+        if self.depth <= 0.0 || self.depth > 1.0 {
+            return Err("Tremolo depth must be greater than 0 and up to 1");
+        }
+
+        Ok(())
+    }
+}
+
+/// Oscillates the volume of the audio.
+pub struct Tremolo {
+    depth: f32,
+    lfo: Lfo
+}
+
+impl Tremolo {
+    pub fn new(config: &TremoloConfig, sample_rate: u32) -> Self {
+        Self {
+            depth: config.depth,
+            lfo: Lfo::new(config.frequency, sample_rate)
+        }
+    }
+}
+
+impl AudioFilter for Tremolo {
+    fn name(&self) -> &'static str {
+        "tremolo"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        for frame in samples.chunks_exact_mut(CHANNELS) {
+            let gain = 1.0 - self.depth * self.lfo.next();
+
+            for sample in frame.iter_mut() {
+                *sample *= gain;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.lfo.phase = 0.0;
+    }
+}
+
+/// Configuration of the [`Vibrato`] filter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct VibratoConfig {
+    /// Oscillations per second.
+    #[serde(default = "default_frequency")]
+    pub frequency: f32,
+    /// How much the pitch changes in every oscillation, from 0 to 1.
+    #[serde(default = "default_depth")]
+    pub depth: f32
+}
+
+impl VibratoConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.frequency <= 0.0 || self.frequency > 14.0 {
+            return Err("Vibrato frequency must be greater than 0 and up to 14 Hz");
+        }
+
+        if self.depth <= 0.0 || self.depth > 1.0 {
+            return Err("Vibrato depth must be greater than 0 and up to 1");
+        }
+
+        Ok(())
+    }
+}
+
+/// Oscillates the pitch of the audio, reading it through a delay line whose length
+/// changes with a low frequency oscillator.
+pub struct Vibrato {
+    lfo: Lfo,
+    /// Delay in frames at the top of every oscillation.
+    max_delay: f32,
+    /// Circular buffer with the last frames.
+    buffer: Vec<[f32; CHANNELS]>,
+    write: usize
+}
+
+impl Vibrato {
+    pub fn new(config: &VibratoConfig, sample_rate: u32) -> Self {
+        let max_delay = VIBRATO_MAX_DELAY * config.depth * sample_rate as f32;
+
+        Self {
+            lfo: Lfo::new(config.frequency, sample_rate),
+            max_delay,
+            // Two extra frames for the interpolation and the frame being written.
+            buffer: vec![[0.0; CHANNELS]; max_delay.ceil() as usize + 2],
+            write: 0
+        }
+    }
+}
+
+impl AudioFilter for Vibrato {
+    fn name(&self) -> &'static str {
+        "vibrato"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        let len = self.buffer.len();
+
+        for frame in samples.chunks_exact_mut(CHANNELS) {
+            self.buffer[self.write].copy_from_slice(frame);
+
+            let delay = self.max_delay * self.lfo.next();
+            let whole = delay as usize;
+            let frac = delay - whole as f32;
+
+            let a = self.buffer[(self.write + len - whole) % len];
+            let b = self.buffer[(self.write + len - whole - 1) % len];
+
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                *sample = a[channel] + (b[channel] - a[channel]) * frac;
+            }
+
+            self.write = (self.write + 1) % len;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.lfo.phase = 0.0;
+        self.buffer.fill([0.0; CHANNELS]);
+        self.write = 0;
+    }
+}
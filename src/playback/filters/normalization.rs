@@ -0,0 +1,240 @@
+use serde::{Deserialize, Serialize};
+use songbird::input::Parsed;
+use symphonia::core::meta::{MetadataRevision, StandardTagKey};
+use super::biquad::{Biquad, Coefficients};
+use super::{CHANNELS, db_to_gain};
+
+/// Reference loudness of ReplayGain 2.0 gains, in LUFS.
+const REPLAY_GAIN_REFERENCE: f32 = -18.0;
+/// Maximum boost applied to quiet tracks, in dB.
+const MAX_BOOST: f32 = 10.0;
+/// Maximum attenuation applied to loud tracks, in dB.
+const MAX_CUT: f32 = -20.0;
+/// Maximum speed the gain changes at while measuring, in dB per second.
+const MAX_CHANGE: f32 = 3.0;
+/// Maximum speed the gain changes at during the first seconds measured, so tracks reach
+/// their gain quickly instead of starting at their original loudness, in dB per second.
+const MAX_INITIAL_CHANGE: f32 = 30.0;
+/// Seconds of audio measured before the gain changes at [`MAX_CHANGE`].
+const INITIAL_PERIOD: f32 = 3.0;
+/// Length of the blocks the loudness is measured in, in seconds.
+const BLOCK_SECONDS: f32 = 0.4;
+/// Blocks quieter than this don't count towards the measured loudness, in LUFS.
+const ABSOLUTE_GATE: f32 = -70.0;
+
+fn default_target() -> f32 {
+    -14.0
+}
+
+/// How the loudness of tracks is normalized.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalizationMode {
+    /// Tracks play at their original loudness.
+    #[default]
+    Off,
+    /// Every track is brought to the target loudness, using the loudness provided by the
+    /// source or its ReplayGain tags, or measuring it while playing if none is available.
+    Track
+}
+
+/// Normalization settings of a player.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Normalization {
+    #[serde(default)]
+    pub mode: NormalizationMode,
+    /// Loudness tracks are normalized to, in LUFS.
+    #[serde(default = "default_target")]
+    pub target: f32
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Self {
+            mode: NormalizationMode::Off,
+            target: default_target()
+        }
+    }
+}
+
+impl Normalization {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if !(-40.0..=0.0).contains(&self.target) {
+            return Err("Normalization target must be between -40 and 0 LUFS");
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the loudness of a track from its ReplayGain tags, in LUFS.
+pub fn replay_gain_loudness(parsed: &mut Parsed) -> Option<f32> {
+    fn from_revision(revision: &MetadataRevision) -> Option<f32> {
+        revision.tags()
+            .iter()
+            .find(|t| t.std_key == Some(StandardTagKey::ReplayGainTrackGain))
+            .and_then(|t| t.value.to_string()
+                .trim()
+                .trim_end_matches("dB")
+                .trim()
+                .parse::<f32>()
+                .ok())
+            .map(|gain| REPLAY_GAIN_REFERENCE - gain)
+    }
+
+    if let Some(loudness) = parsed.format.metadata().current().and_then(from_revision) {
+        return Some(loudness);
+    }
+
+    parsed.meta.get()
+        .and_then(|m| m.current().and_then(from_revision))
+}
+
+/// Applies the gain needed to bring a track to the target loudness.
+pub struct Normalizer {
+    target: f32,
+    sample_rate: u32,
+    /// Loudness of the track in LUFS, if known before playing it.
+    loudness: Option<f32>,
+    /// Loudness measured while playing, only present if the source didn't provide one.
+    meter: Option<LoudnessMeter>,
+    /// Gain currently applied, in dB.
+    gain: f32
+}
+
+impl Normalizer {
+    /// Creates a normalizer for a track, `loudness` is the loudness of the track in LUFS, if known.
+    pub fn new(normalization: &Normalization, loudness: Option<f32>, sample_rate: u32) -> Self {
+        Self {
+            target: normalization.target,
+            sample_rate,
+            loudness,
+            meter: loudness.is_none().then(|| LoudnessMeter::new(sample_rate)),
+            gain: loudness.map(|l| gain_for(normalization.target, l))
+                .unwrap_or(0.0)
+        }
+    }
+
+    /// Changes the target loudness, keeping what was measured so far.
+    pub fn set_target(&mut self, target: f32) {
+        self.target = target;
+
+        if let Some(loudness) = self.loudness {
+            self.gain = gain_for(target, loudness);
+        }
+    }
+
+    pub fn process(&mut self, samples: &mut [f32]) {
+        let Some(meter) = self.meter.as_mut() else {
+            let gain = db_to_gain(self.gain);
+
+            for sample in samples.iter_mut() {
+                *sample *= gain;
+            }
+
+            return;
+        };
+
+        meter.process(samples);
+
+        // While measuring, move the gain towards the measured one, quickly at first and then
+        // slowly, ramping across the samples so there are no jumps in volume.
+        let frames = samples.len() / CHANNELS;
+        let start = db_to_gain(self.gain);
+
+        if let Some(loudness) = meter.loudness() {
+            let speed = if meter.measured() < INITIAL_PERIOD {
+                MAX_INITIAL_CHANGE
+            } else {
+                MAX_CHANGE
+            };
+
+            let max_change = speed * frames as f32 / self.sample_rate as f32;
+            let target = gain_for(self.target, loudness);
+            self.gain += (target - self.gain).clamp(-max_change, max_change);
+        }
+
+        let end = db_to_gain(self.gain);
+
+        for (i, frame) in samples.chunks_exact_mut(CHANNELS).enumerate() {
+            let gain = start + (end - start) * (i as f32 / frames as f32);
+
+            for sample in frame.iter_mut() {
+                *sample *= gain;
+            }
+        }
+    }
+}
+
+/// Simplified EBU R128 integrated loudness meter, using non overlapping blocks and
+/// only the absolute gate.
+struct LoudnessMeter {
+    shelf: Biquad,
+    high_pass: Biquad,
+    scratch: Vec<f32>,
+    block_frames: usize,
+    /// Frames and sum of squares of the current block.
+    block: (usize, f64),
+    /// Number of blocks and sum of their mean squares.
+    integrated: (usize, f64)
+}
+
+impl LoudnessMeter {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            // K-weighting filter stages as described in ITU-R BS.1770
+            shelf: Biquad::new(Coefficients::high_shelf(sample_rate, 1681.97, 0.7072, 4.0)),
+            high_pass: Biquad::new(Coefficients::high_pass(sample_rate, 38.13, 0.5003)),
+            scratch: Vec::new(),
+            block_frames: ((sample_rate as f32 * BLOCK_SECONDS) as usize).max(1),
+            block: (0, 0.0),
+            integrated: (0, 0.0)
+        }
+    }
+
+    fn process(&mut self, samples: &[f32]) {
+        self.scratch.clear();
+        self.scratch.extend_from_slice(samples);
+        self.shelf.process(&mut self.scratch);
+        self.high_pass.process(&mut self.scratch);
+
+        for frame in self.scratch.chunks_exact(CHANNELS) {
+            self.block.0 += 1;
+            self.block.1 += frame.iter().map(|s| (*s as f64).powi(2)).sum::<f64>();
+
+            if self.block.0 == self.block_frames {
+                let mean = self.block.1 / self.block.0 as f64;
+
+                if loudness(mean) > ABSOLUTE_GATE {
+                    self.integrated.0 += 1;
+                    self.integrated.1 += mean;
+                }
+
+                self.block = (0, 0.0);
+            }
+        }
+    }
+
+    /// Seconds of audio counted towards the loudness so far.
+    fn measured(&self) -> f32 {
+        self.integrated.0 as f32 * BLOCK_SECONDS
+    }
+
+    /// Loudness measured so far, in LUFS.
+    fn loudness(&self) -> Option<f32> {
+        if self.integrated.0 == 0 {
+            return None;
+        }
+
+        Some(loudness(self.integrated.1 / self.integrated.0 as f64))
+    }
+}
+
+/// Gain in dB that brings a track with the provided loudness to `target`.
+fn gain_for(target: f32, loudness: f32) -> f32 {
+    (target - loudness).clamp(MAX_CUT, MAX_BOOST)
+}
+
+fn loudness(mean_square: f64) -> f32 {
+    (-0.691 + 10.0 * mean_square.max(1e-12).log10()) as f32
+}
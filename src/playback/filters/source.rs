@@ -0,0 +1,449 @@
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use async_trait::async_trait;
+use songbird::input::{AudioStream, AudioStreamError, Compose, Input, LiveInput, Parsed, RawAdapter};
+use songbird::input::codecs::{get_codec_registry, get_probe};
+use songbird::input::core::io::MediaSource;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{SeekMode, SeekTo};
+use symphonia::core::units::Time;
+use uuid::Uuid;
+use crate::playback::metadata::TrackMetadata;
+use crate::playback::player::transition::Transitions;
+use super::{CHANNELS, FilterChain, Filters};
+use super::fade::{Fade, Fader};
+use super::normalization::{Normalizer, replay_gain_loudness};
+
+/// Sample rate used when the source doesn't report one.
+const DEFAULT_SAMPLE_RATE: u32 = 48_000;
+/// Size in bytes of a single stereo `f32` frame.
+const FRAME_BYTES: u64 = (CHANNELS * std::mem::size_of::<f32>()) as u64;
+
+/// Position of a track in the timeline of its source, this is not affected by filters
+/// that change the playback speed, unlike the position reported by songbird.
+#[derive(Default)]
+pub struct SourcePosition {
+    millis: AtomicU64
+}
+
+impl SourcePosition {
+    pub fn get(&self) -> Duration {
+        Duration::from_millis(self.millis.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, frame: u64, sample_rate: u32) {
+        self.millis.store(frame * 1000 / sample_rate as u64, Ordering::Relaxed);
+    }
+}
+
+/// Lazy input that decodes the wrapped input and feeds its samples through the
+/// filters of a player before handing them to songbird as raw PCM.
+pub struct FilteredInput {
+    inner: Option<Input>,
+    filters: Arc<Filters>,
+    transitions: Arc<Transitions>,
+    /// Id of the songbird track this input belongs to.
+    track: Uuid,
+    position: Arc<SourcePosition>,
+    fade: Arc<Fade>,
+    loudness: Option<f32>,
+    duration: Option<Duration>,
+    /// Positions of the source the track starts and ends at.
+    start_time: Option<Duration>,
+    end_time: Option<Duration>
+}
+
+impl FilteredInput {
+    pub fn wrap(
+        input: Input,
+        filters: Arc<Filters>,
+        transitions: Arc<Transitions>,
+        track: Uuid,
+        meta: &TrackMetadata
+    ) -> Input {
+        Input::Lazy(Box::new(Self {
+            inner: Some(input),
+            filters,
+            transitions,
+            track,
+            position: Arc::clone(&meta.position),
+            fade: Arc::clone(&meta.fade),
+            loudness: meta.loudness,
+            duration: meta.metadata.duration,
+            start_time: meta.start_time,
+            end_time: meta.end_time
+        }))
+    }
+
+    /// Creates and parses the wrapped input, lazy inputs are kept so they can be
+    /// recreated later if songbird needs to.
+    async fn parse(&mut self) -> Result<Parsed, AudioStreamError> {
+        let live = match self.inner.take() {
+            Some(Input::Lazy(mut compose)) => {
+                let res = if compose.should_create_async() {
+                    compose.create_async().await.map(|s| (s, compose))
+                } else {
+                    tokio::task::spawn_blocking(move || compose.create().map(|s| (s, compose)))
+                        .await
+                        .map_err(|e| AudioStreamError::Fail(Box::new(e)))?
+                };
+
+                let (stream, compose) = res?;
+                self.inner = Some(Input::Lazy(compose));
+                LiveInput::Raw(stream)
+            },
+            Some(Input::Live(live, _)) => live,
+            None => return Err(AudioStreamError::Fail("Input can't be recreated".into()))
+        };
+
+        let parsed = tokio::task::spawn_blocking(move || live.promote(get_codec_registry(), get_probe()))
+            .await
+            .map_err(|e| AudioStreamError::Fail(Box::new(e)))?
+            .map_err(|e| AudioStreamError::Fail(Box::new(e)))?;
+
+        match parsed {
+            LiveInput::Parsed(p) => Ok(p),
+            _ => Err(AudioStreamError::Fail("Failed to parse input".into()))
+        }
+    }
+}
+
+#[async_trait]
+impl Compose for FilteredInput {
+    fn create(&mut self) -> Result<AudioStream<Box<dyn MediaSource>>, AudioStreamError> {
+        Err(AudioStreamError::Unsupported)
+    }
+
+    async fn create_async(&mut self) -> Result<AudioStream<Box<dyn MediaSource>>, AudioStreamError> {
+        let parsed = self.parse().await?;
+        let mut source = FilteredSource::new(parsed, self);
+        let sample_rate = source.sample_rate;
+
+        // Seek before any sample is played, so the track never starts from the beginning.
+        if source.start_frame > 0 {
+            source.seek_frame(0)
+                .map_err(|e| AudioStreamError::Fail(Box::new(e)))?;
+        }
+
+        Ok(AudioStream {
+            input: Box::new(RawAdapter::new(source, sample_rate, CHANNELS as u32)) as Box<dyn MediaSource>,
+            hint: None
+        })
+    }
+
+    fn should_create_async(&self) -> bool {
+        true
+    }
+}
+
+/// Media source that outputs the decoded samples of a parsed input, after being
+/// processed by the filter chain, as interleaved stereo `f32` samples.
+pub struct FilteredSource {
+    parsed: Parsed,
+    filters: Arc<Filters>,
+    chain: FilterChain,
+    normalizer: Option<Normalizer>,
+    fader: Fader,
+    fade: Arc<Fade>,
+    /// Loudness of the track in LUFS, if known.
+    loudness: Option<f32>,
+    transitions: Arc<Transitions>,
+    track: Uuid,
+    /// Length of the source in frames, if known.
+    total_frames: Option<u64>,
+    /// Frame the source starts at, positions and seeks are relative to it so the start of
+    /// the clip is the start of the track.
+    start_frame: u64,
+    /// Frame the source ends at, if it ends before its length.
+    end_frame: Option<u64>,
+    /// Frames decoded and frames output by the chain since it was built, filters like
+    /// timescale change how long the rest of the source takes to play.
+    consumed: u64,
+    produced: u64,
+    /// Whether if the player was already told this source is about to end.
+    ending_sent: bool,
+    /// Version of the filters the chain was built with.
+    version: u64,
+    sample_rate: u32,
+    sample_buf: Option<SampleBuffer<f32>>,
+    samples: Vec<f32>,
+    out: Vec<u8>,
+    out_pos: usize,
+    /// Position of the source, in frames.
+    position: u64,
+    shared_position: Arc<SourcePosition>
+}
+
+impl FilteredSource {
+    fn new(mut parsed: Parsed, input: &FilteredInput) -> Self {
+        let sample_rate = parsed.decoder.codec_params().sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE);
+        let loudness = input.loudness.or_else(|| replay_gain_loudness(&mut parsed));
+        let total_frames = parsed.format.tracks()
+            .iter()
+            .find(|t| t.id == parsed.track_id)
+            .and_then(|t| t.codec_params.n_frames)
+            .or_else(|| input.duration.map(|d| (d.as_secs_f64() * sample_rate as f64) as u64));
+
+        let start_frame = input.start_time.map_or(0, |d| (d.as_secs_f64() * sample_rate as f64) as u64);
+        let end_frame = input.end_time.map(|d| (d.as_secs_f64() * sample_rate as f64) as u64);
+        let total_frames = match (total_frames, end_frame) {
+            (Some(total), Some(end)) => Some(total.min(end)),
+            (total, end) => total.or(end)
+        };
+
+        let filters = Arc::clone(&input.filters);
+        let version = filters.version();
+        let chain = filters.build_chain(sample_rate);
+        let normalizer = filters.build_normalizer(loudness, sample_rate);
+
+        Self {
+            parsed,
+            filters,
+            chain,
+            normalizer,
+            fader: Fader::new(),
+            fade: Arc::clone(&input.fade),
+            loudness,
+            transitions: Arc::clone(&input.transitions),
+            track: input.track,
+            total_frames,
+            start_frame,
+            end_frame,
+            consumed: 0,
+            produced: 0,
+            ending_sent: false,
+            version,
+            sample_rate,
+            sample_buf: None,
+            samples: Vec::new(),
+            out: Vec::new(),
+            out_pos: 0,
+            position: 0,
+            shared_position: Arc::clone(&input.position)
+        }
+    }
+
+    /// Position of the source relative to the start of the clip, in frames.
+    fn clip_position(&self) -> u64 {
+        self.position.saturating_sub(self.start_frame)
+    }
+
+    /// Decodes the next packet of the track into `samples`, converted to stereo,
+    /// returns `false` when the source has ended.
+    fn decode_next(&mut self) -> io::Result<bool> {
+        if self.end_frame.is_some_and(|end| self.position >= end) {
+            return Ok(false);
+        }
+
+        loop {
+            let packet = match self.parsed.format.next_packet() {
+                Ok(p) => p,
+                Err(SymphoniaError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => return Ok(false),
+                Err(SymphoniaError::ResetRequired) => {
+                    self.parsed.decoder.reset();
+                    continue;
+                },
+                Err(e) => return Err(io::Error::new(ErrorKind::Other, e))
+            };
+
+            if packet.track_id() != self.parsed.track_id {
+                continue;
+            }
+
+            let decoded = match self.parsed.decoder.decode(&packet) {
+                Ok(d) => d,
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(e) => return Err(io::Error::new(ErrorKind::Other, e))
+            };
+
+            let spec = *decoded.spec();
+            let channels = spec.channels.count();
+
+            if channels == 0 {
+                continue;
+            }
+
+            let required = decoded.capacity() * channels;
+            let buf = match &mut self.sample_buf {
+                Some(buf) if buf.capacity() >= required => buf,
+                buf => buf.insert(SampleBuffer::new(decoded.capacity() as u64, spec))
+            };
+
+            buf.copy_interleaved_ref(decoded);
+
+            self.samples.clear();
+
+            if channels == 1 {
+                for s in buf.samples() {
+                    self.samples.extend([*s, *s]);
+                }
+            } else {
+                for frame in buf.samples().chunks_exact(channels) {
+                    self.samples.extend([frame[0], frame[1]]);
+                }
+            }
+
+            // Cut the packet that goes past the end of the clip.
+            if let Some(end) = self.end_frame {
+                let left = end.saturating_sub(self.position) as usize;
+                self.samples.truncate(left * CHANNELS);
+            }
+
+            self.position += (self.samples.len() / CHANNELS) as u64;
+            self.shared_position.set(self.clip_position(), self.sample_rate);
+            return Ok(true);
+        }
+    }
+
+    /// Fills the output buffer with the next processed samples, returns `false` when
+    /// the source has ended.
+    fn fill(&mut self) -> io::Result<bool> {
+        if !self.decode_next()? {
+            // Durations aren't always accurate, so make sure the player gets to start the
+            // transition anyway.
+            if !self.ending_sent && self.transitions.lead().is_some() {
+                self.ending_sent = true;
+                self.transitions.ending(self.track);
+            }
+
+            return Ok(false);
+        }
+
+        let version = self.filters.version();
+        if version != self.version {
+            let previous = std::mem::replace(&mut self.chain, FilterChain::new());
+            let (chain, changed) = self.filters.rebuild_chain(previous, self.sample_rate);
+            self.chain = chain;
+            self.filters.update_normalizer(&mut self.normalizer, self.loudness, self.sample_rate);
+            self.version = version;
+
+            // Keep measuring how fast the chain plays if it is the same, so a change while
+            // the track is ending doesn't move the start of the transition.
+            if changed {
+                self.consumed = 0;
+                self.produced = 0;
+            }
+        }
+
+        if let Some(normalizer) = self.normalizer.as_mut() {
+            normalizer.process(&mut self.samples);
+        }
+
+        self.consumed += (self.samples.len() / CHANNELS) as u64;
+        self.chain.process(&mut self.samples);
+        self.produced += (self.samples.len() / CHANNELS) as u64;
+
+        self.fader.update(&self.fade, self.sample_rate);
+        self.fader.process(&mut self.samples);
+        self.check_ending();
+
+        self.out.clear();
+        self.out_pos = 0;
+
+        for sample in self.samples.iter() {
+            self.out.extend_from_slice(&sample.to_le_bytes());
+        }
+
+        Ok(true)
+    }
+
+    /// Tells the player this source is about to end once the time left to play is
+    /// shorter than the lead time of the transition.
+    fn check_ending(&mut self) {
+        if self.ending_sent {
+            return;
+        }
+
+        let (Some(total), Some(lead)) = (self.total_frames, self.transitions.lead()) else {
+            return;
+        };
+
+        let ratio = if self.produced == 0 {
+            1.0
+        } else {
+            self.consumed as f64 / self.produced as f64
+        };
+        let lead = (lead.as_secs_f64() * self.sample_rate as f64 * ratio) as u64;
+
+        if total.saturating_sub(self.position) <= lead {
+            self.ending_sent = true;
+            self.transitions.ending(self.track);
+        }
+    }
+
+    /// Seeks the source to the provided frame, relative to the start of the clip and up to
+    /// its end.
+    fn seek_frame(&mut self, frame: u64) -> io::Result<()> {
+        let mut frame = self.start_frame + frame;
+
+        if let Some(end) = self.end_frame {
+            frame = frame.min(end);
+        }
+
+        let rate = self.sample_rate as u64;
+        let time = Time::new(frame / rate, (frame % rate) as f64 / rate as f64);
+
+        self.parsed.format.seek(SeekMode::Accurate, SeekTo::Time {
+            time,
+            track_id: Some(self.parsed.track_id)
+        }).map_err(|e| io::Error::new(ErrorKind::Other, e))?;
+
+        self.parsed.decoder.reset();
+        self.chain.reset();
+        self.out.clear();
+        self.out_pos = 0;
+        self.position = frame;
+        self.shared_position.set(self.clip_position(), self.sample_rate);
+        self.ending_sent = false;
+
+        Ok(())
+    }
+}
+
+impl Read for FilteredSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.out_pos >= self.out.len() {
+            if !self.fill()? {
+                return Ok(0);
+            }
+        }
+
+        let len = std::cmp::min(buf.len(), self.out.len() - self.out_pos);
+        buf[..len].copy_from_slice(&self.out[self.out_pos..self.out_pos + len]);
+        self.out_pos += len;
+
+        Ok(len)
+    }
+}
+
+impl Seek for FilteredSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        // Positions are always expressed in source frames from the start of the clip, so
+        // filters changing the number of samples don't affect seeking.
+        let current = self.clip_position() * FRAME_BYTES;
+        let target = match pos {
+            SeekFrom::Start(p) => p,
+            SeekFrom::Current(0) => return Ok(current),
+            SeekFrom::Current(offset) => current.checked_add_signed(offset)
+                .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "Seek before start of source"))?,
+            SeekFrom::End(_) => return Err(io::Error::new(ErrorKind::Unsupported, "Seeking from end is not supported"))
+        };
+
+        self.seek_frame(target / FRAME_BYTES)?;
+
+        Ok(self.clip_position() * FRAME_BYTES)
+    }
+}
+
+impl MediaSource for FilteredSource {
+    fn is_seekable(&self) -> bool {
+        self.parsed.supports_backseek
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
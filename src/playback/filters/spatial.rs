@@ -0,0 +1,203 @@
+use std::f32::consts::{FRAC_PI_4, SQRT_2, TAU};
+use serde::{Deserialize, Serialize};
+use super::{AudioFilter, CHANNELS};
+use super::biquad::{Biquad, Coefficients};
+
+fn default_rotation() -> f32 {
+    0.2
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+fn default_filter_band() -> f32 {
+    220.0
+}
+
+fn default_filter_width() -> f32 {
+    100.0
+}
+
+/// Configuration of the [`Rotation`] filter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct RotationConfig {
+    /// Number of full rotations around the listener per second.
+    #[serde(default = "default_rotation")]
+    pub rotation_hz: f32
+}
+
+impl RotationConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if !(0.0..=5.0).contains(&self.rotation_hz) {
+            return Err("Rotation speed must be between 0.0 and 5.0 Hz");
+        }
+
+        Ok(())
+    }
+}
+
+/// Pans the audio back and forth between both channels, giving the impression of the
+/// audio rotating around the listener (also known as 8D audio).
+pub struct Rotation {
+    /// Phase increment per frame.
+    step: f32,
+    phase: f32
+}
+
+impl Rotation {
+    pub fn new(config: &RotationConfig, sample_rate: u32) -> Self {
+        Self {
+            step: TAU * config.rotation_hz / sample_rate as f32,
+            phase: 0.0
+        }
+    }
+}
+
+impl AudioFilter for Rotation {
+    fn name(&self) -> &'static str {
+        "rotation"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        for frame in samples.chunks_exact_mut(CHANNELS) {
+            // Equal power panning, scaled so the center keeps the original level.
+            let angle = (self.phase.sin() + 1.0) * FRAC_PI_4;
+            frame[0] *= angle.cos() * SQRT_2;
+            frame[1] *= angle.sin() * SQRT_2;
+
+            self.phase = (self.phase + self.step) % TAU;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0.0;
+    }
+}
+
+/// Configuration of the [`ChannelMix`] filter, every factor goes from 0 to 1.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ChannelMixConfig {
+    #[serde(default = "one")]
+    pub left_to_left: f32,
+    #[serde(default)]
+    pub left_to_right: f32,
+    #[serde(default)]
+    pub right_to_left: f32,
+    #[serde(default = "one")]
+    pub right_to_right: f32
+}
+
+impl ChannelMixConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let factors = [self.left_to_left, self.left_to_right, self.right_to_left, self.right_to_right];
+
+        if factors.iter().any(|f| !(0.0..=1.0).contains(f)) {
+            return Err("Channel mix factors must be between 0.0 and 1.0");
+        }
+
+        Ok(())
+    }
+}
+
+/// Mixes both channels into each other, setting every factor to `0.5` turns the audio into
+/// mono, and swapping them swaps the channels.
+pub struct ChannelMix {
+    config: ChannelMixConfig
+}
+
+impl ChannelMix {
+    pub fn new(config: &ChannelMixConfig) -> Self {
+        Self {
+            config: config.clone()
+        }
+    }
+}
+
+impl AudioFilter for ChannelMix {
+    fn name(&self) -> &'static str {
+        "channel_mix"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        let c = &self.config;
+
+        for frame in samples.chunks_exact_mut(CHANNELS) {
+            let (left, right) = (frame[0], frame[1]);
+            frame[0] = left * c.left_to_left + right * c.right_to_left;
+            frame[1] = left * c.left_to_right + right * c.right_to_right;
+        }
+    }
+}
+
+/// Configuration of the [`Karaoke`] filter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct KaraokeConfig {
+    /// How much of the center of the stereo image is removed, from 0 to 1.
+    #[serde(default = "one")]
+    pub level: f32,
+    /// How much of the center is kept inside the filter band, from 0 to 1.
+    #[serde(default = "one")]
+    pub mono_level: f32,
+    /// Center frequency in Hz of the band that is kept.
+    #[serde(default = "default_filter_band")]
+    pub filter_band: f32,
+    /// Width in Hz of the band that is kept.
+    #[serde(default = "default_filter_width")]
+    pub filter_width: f32
+}
+
+impl KaraokeConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if !(0.0..=1.0).contains(&self.level) || !(0.0..=1.0).contains(&self.mono_level) {
+            return Err("Karaoke levels must be between 0.0 and 1.0");
+        }
+
+        if !(20.0..=20000.0).contains(&self.filter_band) || !(1.0..=20000.0).contains(&self.filter_width) {
+            return Err("Karaoke filter band must be between 20 and 20000 Hz, and its width between 1 and 20000 Hz");
+        }
+
+        Ok(())
+    }
+}
+
+/// Removes the audio panned to the center, where vocals usually are, while keeping the
+/// center inside a frequency band, so bass and kick drums aren't lost along with them.
+pub struct Karaoke {
+    level: f32,
+    mono_level: f32,
+    band: Biquad
+}
+
+impl Karaoke {
+    pub fn new(config: &KaraokeConfig, sample_rate: u32) -> Self {
+        let frequency = config.filter_band.min(sample_rate as f32 * 0.45);
+
+        Self {
+            level: config.level,
+            mono_level: config.mono_level,
+            band: Biquad::new(Coefficients::band_pass(sample_rate, frequency, frequency / config.filter_width))
+        }
+    }
+}
+
+impl AudioFilter for Karaoke {
+    fn name(&self) -> &'static str {
+        "karaoke"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        for frame in samples.chunks_exact_mut(CHANNELS) {
+            let center = (frame[0] + frame[1]) / 2.0;
+            let kept = self.band.process_sample(0, center) * self.mono_level;
+
+            for sample in frame.iter_mut() {
+                *sample = *sample - center * self.level + kept * self.level;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.band.reset();
+    }
+}
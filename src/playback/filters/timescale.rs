@@ -0,0 +1,210 @@
+use std::f32::consts::PI;
+use serde::{Deserialize, Serialize};
+use super::{AudioFilter, CHANNELS};
+
+fn one() -> f64 {
+    1.0
+}
+
+/// Configuration of the [`Timescale`] filter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct TimescaleConfig {
+    /// Playback speed, changes the tempo without changing the pitch.
+    #[serde(default = "one")]
+    pub speed: f64,
+    /// Pitch multiplier, changes the pitch without changing the tempo.
+    #[serde(default = "one")]
+    pub pitch: f64,
+    /// Playback rate, changes both the tempo and the pitch.
+    #[serde(default = "one")]
+    pub rate: f64
+}
+
+impl TimescaleConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        for value in [self.speed, self.pitch, self.rate] {
+            if !(0.1..=5.0).contains(&value) {
+                return Err("Timescale values must be between 0.1 and 5.0");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Filter that changes the speed and pitch of the audio.
+///
+/// The audio is first resampled by `pitch * rate`, which changes both tempo and pitch,
+/// and then stretched by `speed / pitch` to correct the tempo without touching the pitch.
+pub struct Timescale {
+    resampler: Option<Resampler>,
+    stretch: Option<TimeStretch>
+}
+
+impl Timescale {
+    pub fn new(config: &TimescaleConfig, sample_rate: u32) -> Self {
+        let ratio = config.pitch * config.rate;
+        let tempo = config.speed / config.pitch;
+
+        Self {
+            resampler: (ratio != 1.0).then(|| Resampler::new(ratio)),
+            stretch: (tempo != 1.0).then(|| TimeStretch::new(tempo, sample_rate))
+        }
+    }
+}
+
+impl AudioFilter for Timescale {
+    fn name(&self) -> &'static str {
+        "timescale"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        if let Some(resampler) = self.resampler.as_mut() {
+            resampler.process(samples);
+        }
+
+        if let Some(stretch) = self.stretch.as_mut() {
+            stretch.process(samples);
+        }
+    }
+
+    fn reset(&mut self) {
+        if let Some(resampler) = self.resampler.as_mut() {
+            resampler.reset();
+        }
+
+        if let Some(stretch) = self.stretch.as_mut() {
+            stretch.reset();
+        }
+    }
+}
+
+/// Linear interpolation resampler, reads `ratio` input frames per output frame.
+struct Resampler {
+    ratio: f64,
+    /// Position of the next output frame, relative to `last`.
+    pos: f64,
+    /// Last frame of the previous call, needed to interpolate across calls.
+    last: [f32; CHANNELS],
+    input: Vec<f32>
+}
+
+impl Resampler {
+    fn new(ratio: f64) -> Self {
+        Self {
+            ratio,
+            pos: 0.0,
+            last: [0.0; CHANNELS],
+            input: Vec::new()
+        }
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        std::mem::swap(samples, &mut self.input);
+        samples.clear();
+
+        let frames = self.input.len() / CHANNELS;
+
+        if frames == 0 {
+            return;
+        }
+
+        // Index 0 is the last frame of the previous call, the rest are the input frames.
+        let frame = |idx: usize, channel: usize| if idx == 0 {
+            self.last[channel]
+        } else {
+            self.input[(idx - 1) * CHANNELS + channel]
+        };
+
+        while self.pos < frames as f64 {
+            let idx = self.pos as usize;
+            let frac = (self.pos - idx as f64) as f32;
+
+            for channel in 0..CHANNELS {
+                let a = frame(idx, channel);
+                let b = frame(idx + 1, channel);
+                samples.push(a + (b - a) * frac);
+            }
+
+            self.pos += self.ratio;
+        }
+
+        self.pos -= frames as f64;
+        self.last.copy_from_slice(&self.input[(frames - 1) * CHANNELS..frames * CHANNELS]);
+    }
+
+    fn reset(&mut self) {
+        self.pos = 0.0;
+        self.last = [0.0; CHANNELS];
+        self.input.clear();
+    }
+}
+
+/// Overlap-add time stretcher, changes the tempo of the audio without changing its pitch.
+struct TimeStretch {
+    tempo: f64,
+    /// Hann window applied to every grain.
+    window: Vec<f32>,
+    /// Frames not yet consumed.
+    input: Vec<f32>,
+    /// Position of the next grain inside `input`, in frames.
+    pos: f64,
+    /// Sum of the grains that haven't been output yet.
+    overlap: Vec<f32>
+}
+
+impl TimeStretch {
+    fn new(tempo: f64, sample_rate: u32) -> Self {
+        // Grains of ~40ms, with an even number of frames so hops are exactly half a window.
+        let size = ((sample_rate / 25) as usize).max(2) & !1;
+        let window = (0..size)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / size as f32).cos())
+            .collect();
+
+        Self {
+            tempo,
+            window,
+            input: Vec::new(),
+            pos: 0.0,
+            overlap: vec![0.0; size * CHANNELS]
+        }
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        let size = self.window.len();
+        let hop = size / 2;
+
+        self.input.extend_from_slice(samples);
+        samples.clear();
+
+        loop {
+            let start = self.pos as usize;
+
+            if (start + size) * CHANNELS > self.input.len() {
+                break;
+            }
+
+            let grain = &self.input[start * CHANNELS..(start + size) * CHANNELS];
+
+            for (i, (out, sample)) in self.overlap.iter_mut().zip(grain).enumerate() {
+                *out += sample * self.window[i / CHANNELS];
+            }
+
+            samples.extend_from_slice(&self.overlap[..hop * CHANNELS]);
+            self.overlap.copy_within(hop * CHANNELS.., 0);
+            self.overlap[(size - hop) * CHANNELS..].fill(0.0);
+
+            self.pos += hop as f64 * self.tempo;
+        }
+
+        let consumed = std::cmp::min(self.pos as usize, self.input.len() / CHANNELS);
+        self.input.drain(..consumed * CHANNELS);
+        self.pos -= consumed as f64;
+    }
+
+    fn reset(&mut self) {
+        self.input.clear();
+        self.pos = 0.0;
+        self.overlap.fill(0.0);
+    }
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use super::AudioFilter;
+
+/// Configuration of the [`Volume`] filter.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct VolumeConfig {
+    /// Multiplier applied to every sample, `1.0` keeps the original level.
+    pub volume: f32
+}
+
+impl VolumeConfig {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if !(0.0..=5.0).contains(&self.volume) {
+            return Err("Volume filter must be between 0.0 and 5.0");
+        }
+
+        Ok(())
+    }
+}
+
+/// Filter that multiplies every sample by a constant gain.
+pub struct Volume {
+    volume: f32
+}
+
+impl Volume {
+    pub fn new(config: &VolumeConfig) -> Self {
+        Self {
+            volume: config.volume
+        }
+    }
+}
+
+impl AudioFilter for Volume {
+    fn name(&self) -> &'static str {
+        "volume"
+    }
+
+    fn process(&mut self, samples: &mut Vec<f32>) {
+        for sample in samples.iter_mut() {
+            *sample *= self.volume;
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use bytes::Bytes;
 use songbird::tracks::TrackHandle;
+use uuid::Uuid;
 use crate::api::model::play::PlaySource;
+use crate::api::model::track::Track;
 use crate::playback::metadata::TrackMetadata;
+use crate::source::ListedTrack;
 
 pub enum MinimalSource {
     Link {
@@ -25,6 +28,16 @@ impl From<PlaySource> for MinimalSource {
     }
 }
 
+impl MinimalSource {
+    pub fn link(&self) -> Option<&str> {
+        match self {
+            Self::Link {link, ..} => Some(link),
+            Self::Http(link) => Some(link),
+            Self::Bytes(_) => None
+        }
+    }
+}
+
 pub struct HandleWithSource {
     pub handle: TrackHandle,
     pub source: MinimalSource
@@ -38,6 +51,20 @@ impl HandleWithSource {
         }
     }
 
+    /// Id of the queue entry.
+    pub fn id(&self) -> Uuid {
+        self.handle.uuid()
+    }
+
+    /// The track as a pending track with the same id, to enqueue it again later.
+    pub async fn to_pending(&self) -> PendingTrack {
+        PendingTrack {
+            id: self.id(),
+            source: self.full_source().await,
+            track: self.handle.data::<TrackMetadata>().track()
+        }
+    }
+
     pub async fn full_source(&self) -> PlaySource {
         let track = self.handle.data::<TrackMetadata>().track();
 
@@ -57,3 +84,91 @@ impl HandleWithSource {
         }
     }
 }
+
+/// A queued track whose source is resolved once it is about to become the next track, also
+/// used to enqueue tracks again when repeating the queue.
+#[derive(Clone)]
+pub struct PendingTrack {
+    /// Id of the queue entry, kept by the track once resolved.
+    pub id: Uuid,
+    pub source: PlaySource,
+    /// Track sent to clients until the source is resolved.
+    pub track: Track
+}
+
+impl PendingTrack {
+    /// Creates a pending track sent to clients as the track listed for the source, see
+    /// [`ListedTrack`].
+    pub fn new(source: PlaySource) -> Self {
+        let ListedTrack { source, track } = source.into();
+        Self::with_track(source, track)
+    }
+
+    /// Creates a pending track with the id and track of the provided metadata.
+    pub fn from_metadata(meta: &TrackMetadata, source: PlaySource) -> Self {
+        Self {
+            id: meta.id,
+            source,
+            track: meta.track()
+        }
+    }
+
+    /// Creates a pending track sent to clients as the provided track until it is resolved.
+    pub fn with_track(source: PlaySource, track: Track) -> Self {
+        let id = Uuid::new_v4();
+
+        Self {
+            id,
+            track: Track {
+                id: Some(id),
+                ..track
+            },
+            source
+        }
+    }
+}
+
+/// An entry of the queue, resolved or not.
+pub enum QueueEntry {
+    Resolved(HandleWithSource),
+    Pending(PendingTrack)
+}
+
+impl QueueEntry {
+    /// Id of the queue entry.
+    pub fn id(&self) -> Uuid {
+        match self {
+            Self::Resolved(t) => t.id(),
+            Self::Pending(t) => t.id
+        }
+    }
+
+    pub fn track(&self) -> Track {
+        match self {
+            Self::Resolved(t) => t.handle.data::<TrackMetadata>().track(),
+            Self::Pending(t) => t.track.clone()
+        }
+    }
+
+    /// Link of the source of the entry, if it is provided by link.
+    pub fn link(&self) -> Option<&str> {
+        match self {
+            Self::Resolved(t) => t.source.link(),
+            Self::Pending(t) => t.source.link()
+        }
+    }
+
+    pub async fn to_pending(&self) -> PendingTrack {
+        match self {
+            Self::Resolved(t) => t.to_pending().await,
+            Self::Pending(t) => t.clone()
+        }
+    }
+
+    /// Stops the track if it was resolved.
+    pub fn stop(&self) {
+        if let Self::Resolved(t) = self {
+            let _ = t.handle.stop();
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::AbortHandle;
+use tracing::info;
+use crate::api::model::gateway::{Outgoing, OutgoingEvent};
+use crate::api::session::Session;
+use crate::config::IdleAction;
+
+/// How often the players of a session are checked.
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Checks the players of the session every few seconds, independently of their calls, so
+/// players without tracks or a voice connection are checked too. Returns `None` if idle
+/// detection is disabled, the task ends once the session is dropped.
+pub fn spawn_checker(session: &Arc<Session>) -> Option<AbortHandle> {
+    let timeout = session.playback.player_options.idle_timeout;
+
+    if timeout == 0 {
+        return None;
+    }
+
+    let session = Arc::downgrade(session);
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let Some(session) = session.upgrade() else {
+                break;
+            };
+
+            check_players(&session, Duration::from_secs(timeout)).await;
+        }
+    }).abort_handle())
+}
+
+/// Sends a `player_idle` event for the players that have been idle for `timeout`, applying
+/// the configured idle action to them.
+async fn check_players(session: &Arc<Session>, timeout: Duration) {
+    let players = session.playback.players.iter()
+        .map(|entry| (*entry.key(), Arc::clone(entry.value())))
+        .collect::<Vec<_>>();
+
+    let action = session.playback.player_options.idle_action;
+
+    for (guild, player) in players {
+        let (idle, disconnected) = {
+            let mut lock = player.lock().await;
+            (lock.check_idle(timeout), lock.disconnected.is_some())
+        };
+
+        let Some(idle) = idle else {
+            continue;
+        };
+
+        info!("Player for guild {guild} is idle");
+
+        let _ = session.playback.sender.send(Outgoing::Event {
+            guild_id: guild.0.get(),
+            event: OutgoingEvent::PlayerIdle {
+                idle: idle.as_millis() as u64,
+                action
+            }
+        });
+
+        match action {
+            IdleAction::None => {},
+            // Players that already left keep the time they have left to connect again.
+            IdleAction::Disconnect if disconnected => {},
+            IdleAction::Disconnect => {
+                session.playback.leave_player(guild, session).await;
+            },
+            IdleAction::Destroy => {
+                let _ = session.playback.destroy_player(guild).await;
+            }
+        }
+    }
+}
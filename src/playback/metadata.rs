@@ -1,19 +1,62 @@
+use std::sync::Arc;
+use std::time::Duration;
+use serde_json::Value;
 use songbird::input::AuxMetadata;
 use typemap_rev::TypeMapKey;
+use uuid::Uuid;
 use crate::api::model::track::Track;
+use crate::playback::filters::fade::Fade;
+use crate::playback::filters::source::SourcePosition;
 
 
 /// Data inserted to all track handles.
 pub struct TrackMetadata {
+    /// Id of the queue entry, also used as the id of the songbird track.
+    pub id: Uuid,
     /// Metadata of the track.
     pub metadata: AuxMetadata,
     /// The guild the track belongs to.
-    pub guild: u64
+    pub guild: u64,
+    /// Position of the track, see [`SourcePosition`].
+    pub position: Arc<SourcePosition>,
+    /// Loudness of the track in LUFS, if provided by the source.
+    pub loudness: Option<f32>,
+    /// Fades the track in and out.
+    pub fade: Arc<Fade>,
+    /// Data provided by the client when playing the track.
+    pub user_data: Option<Value>,
+    /// Position of the source the track starts playing from.
+    pub start_time: Option<Duration>,
+    /// Position of the source the track ends at.
+    pub end_time: Option<Duration>,
+    /// Whether if the track is an overlay played on top of the queue.
+    pub overlay: bool
 }
 
 impl TrackMetadata {
+    pub fn new(metadata: AuxMetadata, guild: u64) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            metadata,
+            guild,
+            position: Default::default(),
+            loudness: None,
+            fade: Default::default(),
+            user_data: None,
+            start_time: None,
+            end_time: None,
+            overlay: false
+        }
+    }
+
     pub fn track(&self) -> Track {
-        (&self.metadata).into()
+        Track {
+            id: Some(self.id),
+            user_data: self.user_data.clone(),
+            start_time: self.start_time.map(|d| d.as_millis() as u64),
+            end_time: self.end_time.map(|d| d.as_millis() as u64),
+            ..(&self.metadata).into()
+        }
     }
 }
 
@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use std::time::Duration;
 use dashmap::DashMap;
 use parking_lot::Mutex;
 use songbird::Config;
@@ -8,7 +9,9 @@ use songbird::error::ConnectionError;
 use tracing::info;
 use crate::api::session::Session;
 use crate::channel::{Receiver, Sender};
+use crate::config::{FilterOptions, PlayerOptions};
 use crate::mutex::TicketedMutex;
+use crate::playback::filters::FilterConfig;
 use crate::playback::player::handler::PlayerHandler;
 use crate::playback::player::Player;
 use crate::ptr::SharedPtr;
@@ -17,8 +20,9 @@ use crate::source::Sources;
 pub mod metadata;
 pub mod events;
 pub mod filters;
+pub mod idle;
 pub mod player;
-mod handle;
+pub mod handle;
 
 pub struct Playback {
     pub players: DashMap<GuildId, Arc<TicketedMutex<Player>>>,
@@ -26,11 +30,18 @@ pub struct Playback {
     pub sender: Sender,
     pub user_id: UserId,
     pub disposer: DisposalThread,
-    pub sources: SharedPtr<Sources>
+    pub sources: SharedPtr<Sources>,
+    pub filter_options: Arc<FilterOptions>,
+    pub player_options: Arc<PlayerOptions>
 }
 
 impl Playback {
-    pub fn new(user_id: impl Into<UserId>, sources: SharedPtr<Sources>) -> Self {
+    pub fn new(
+        user_id: impl Into<UserId>,
+        sources: SharedPtr<Sources>,
+        filter_options: Arc<FilterOptions>,
+        player_options: Arc<PlayerOptions>
+    ) -> Self {
         let (tx, rx) = crate::channel::new();
 
         Self {
@@ -39,7 +50,9 @@ impl Playback {
             receiver: Mutex::new(Some(rx)),
             user_id: user_id.into(),
             disposer: DisposalThread::run(),
-            sources
+            sources,
+            filter_options,
+            player_options
         }
     }
 
@@ -69,8 +82,13 @@ impl Playback {
                     .disposer(self.disposer.clone()),
                 self.sender.clone()
             );
+            player.session = Arc::downgrade(&s);
             player.register_events(s).await;
 
+            if self.filter_options.limiter {
+                player.filters.set_defaults(vec![FilterConfig::Limiter(Default::default())]);
+            }
+
             info!("Created player for guild {guild}");
 
             let player = Arc::new(TicketedMutex::new(player));
@@ -90,6 +108,40 @@ impl Playback {
         write.destroy().await
     }
 
+    /// Leaves the voice channel keeping the player of the guild, it is destroyed if it doesn't
+    /// connect again within the configured time. Returns whether if the player exists.
+    pub async fn leave_player(&self, g: impl Into<GuildId>, session: &Arc<Session>) -> bool {
+        let guild = g.into();
+        let Some(player) = self.get_player(guild) else {
+            return false;
+        };
+
+        let ttl = self.player_options.disconnected_ttl;
+        let expiry = (ttl > 0).then(|| {
+            let session = Arc::downgrade(session);
+            let weak = Arc::downgrade(&player);
+
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(ttl)).await;
+
+                let (Some(session), Some(player)) = (session.upgrade(), weak.upgrade()) else {
+                    return;
+                };
+
+                // Connecting again aborts this task, unless it was already waiting for the lock.
+                if player.lock().await.disconnected.take().is_none() {
+                    return;
+                }
+
+                info!("Destroying disconnected player for guild {guild}");
+                let _ = session.playback.destroy_player(guild).await;
+            }).abort_handle()
+        });
+
+        player.lock().await.leave(expiry);
+        true
+    }
+
     pub async fn destroy(&self) {
         let keys = self.players.iter()
             .map(|i| *i.key())
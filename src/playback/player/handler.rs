@@ -4,7 +4,7 @@ use async_trait::async_trait;
 use futures_util::FutureExt;
 use songbird::{Event, EventContext, EventHandler, TrackEvent};
 use songbird::events::context_data::{ConnectData, DisconnectData};
-use songbird::tracks::{TrackHandle, TrackState};
+use songbird::tracks::{PlayMode, TrackHandle, TrackState};
 use crate::mutex::TicketedMutex;
 
 use super::Player;
@@ -21,9 +21,27 @@ impl PlayerHandler {
             player.lock().now_or_never().unwrap_unchecked()
         };
 
+        lock.this = Arc::downgrade(&player);
+
         lock.driver.add_global_event(TrackEvent::End.into(), Self {
             player: Arc::clone(&player)
         });
+
+        lock.driver.add_global_event(TrackEvent::Error.into(), Self {
+            player: Arc::clone(&player)
+        });
+
+        if let Some(mut receiver) = lock.transition_receiver.take() {
+            // Weak so the player can be dropped, the sender lives inside of it.
+            let player = Arc::downgrade(&player);
+
+            tokio::spawn(async move {
+                while let Some(track) = receiver.recv().await {
+                    let Some(player) = player.upgrade() else { break; };
+                    player.lock().await.start_transition(track).await;
+                }
+            });
+        }
     }
 }
 
@@ -42,10 +60,26 @@ impl EventHandler for PlayerHandler {
 
 impl PlayerHandler {
     async fn handle_track(&self, data: &[(&TrackState, &TrackHandle)]) -> Option<Event> {
-        let &[(_, handle), ..] = data else { return None; };
+        let &[(state, handle), ..] = data else { return None; };
 
         let mut player = self.player.lock().await;
 
+        if player.overlay_ended(handle.uuid()) {
+            return None;
+        }
+
+        if matches!(state.playing, PlayMode::Errored(_)) {
+            // Errors are only listened to so overlays that fail stop ducking the music.
+            return None;
+        }
+
+        if player.queue.ending.as_ref().is_some_and(|t| t.handle.uuid() == handle.uuid()) {
+            // The track that was fading out after a transition ended, the next one is already
+            // playing.
+            player.queue.ending.take();
+            return None;
+        }
+
         let Some(current) = &player.queue.current else { return None; };
         let current_id = current.handle.uuid();
 
@@ -0,0 +1,56 @@
+use std::collections::VecDeque;
+use crate::api::model::play::PlaySource;
+use crate::api::model::track::Track;
+use crate::playback::handle::HandleWithSource;
+use crate::playback::metadata::TrackMetadata;
+
+/// Maximum number of tracks remembered by a player, the oldest ones are dropped first.
+const HISTORY_SIZE: usize = 50;
+
+/// A track that was played.
+pub struct HistoryEntry {
+    /// Source used to play the track again.
+    pub source: PlaySource,
+    /// The track as it was sent to clients.
+    pub track: Track
+}
+
+/// Tracks played by a player, the most recent one first.
+#[derive(Default)]
+pub struct History {
+    entries: VecDeque<HistoryEntry>
+}
+
+impl History {
+    /// Remembers a track that stopped being the current one.
+    pub async fn push(&mut self, played: &HandleWithSource) {
+        if self.entries.len() >= HISTORY_SIZE {
+            self.entries.pop_back();
+        }
+
+        self.entries.push_front(HistoryEntry {
+            source: played.full_source().await,
+            track: played.handle.data::<TrackMetadata>().track()
+        });
+    }
+
+    /// The most recently played track.
+    pub fn last(&self) -> Option<&HistoryEntry> {
+        self.entries.front()
+    }
+
+    /// Takes the most recently played track.
+    pub fn pop(&mut self) -> Option<HistoryEntry> {
+        self.entries.pop_front()
+    }
+
+    /// Puts back a track taken with [`History::pop`].
+    pub fn restore(&mut self, entry: HistoryEntry) {
+        self.entries.push_front(entry);
+        self.entries.truncate(HISTORY_SIZE);
+    }
+
+    pub fn tracks(&self) -> impl Iterator<Item = &Track> {
+        self.entries.iter().map(|e| &e.track)
+    }
+}
@@ -1,28 +1,73 @@
 pub mod handler;
+pub mod history;
+pub mod overlay;
+pub mod policy;
 pub mod queue;
+pub mod timer;
+pub mod transition;
 
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 use songbird::{Config, ConnectionInfo, Driver};
 use songbird::error::{ConnectionError, ControlError};
 use songbird::id::{ChannelId, GuildId};
 use songbird::input::Input;
-use songbird::tracks::{Track as SongbirdTrack, TrackHandle};
+use songbird::tracks::{LoopState, Track as SongbirdTrack, TrackHandle, TrackResult};
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::task::AbortHandle;
 use tracing::{info, instrument, warn};
+use uuid::Uuid;
 use crate::playback::metadata::TrackMetadata;
-use crate::api::model::player::Player as PlayerModel;
+use crate::api::model::player::{Player as PlayerModel, QueuePage};
 use queue::Queue;
+use history::History;
+use crate::api::error::IntoResponseError;
+use crate::api::session::Session;
 use crate::api::model::gateway::{Outgoing, OutgoingEvent};
 use crate::api::model::play::PlaySource;
-use crate::api::model::track::{Track as TrackModel, Track};
+use crate::api::model::track::Track as TrackModel;
 use crate::channel::Sender;
 use crate::ext::{AsyncIteratorExt, AsyncOptionExt};
 use crate::metrics::metrics;
-use crate::playback::handle::HandleWithSource;
+use crate::mutex::TicketedMutex;
+use crate::playback::filters::Filters;
+use crate::playback::filters::source::FilteredInput;
+use crate::playback::handle::{HandleWithSource, PendingTrack, QueueEntry};
+use crate::playback::player::overlay::{Ducking, Overlay};
+use crate::playback::player::policy::QueuePolicy;
 use crate::playback::player::queue::RepeatMode;
+use crate::playback::player::timer::{ActiveSleepTimer, SleepTimer};
+use crate::playback::player::transition::Transitions;
 use crate::ptr::SharedPtr;
-use crate::source::Sources;
+use crate::source::{ListedTrack, Playable, Recommender, Sources};
+
+/// Maximum number of tracks enqueued by autoplay at once.
+const AUTOPLAY_TRACKS: usize = 10;
+
+/// Tracks how long a player has been idle.
+struct Idle {
+    since: Instant,
+    /// Whether if the idle event was already sent for this idle period.
+    notified: bool
+}
+
+/// State of a player that left the voice channel keeping its queue.
+pub struct Disconnected {
+    /// Whether to resume the playback once connected again.
+    resume: bool,
+    /// Task that destroys the player if it doesn't connect again in time.
+    expiry: Option<AbortHandle>
+}
+
+impl Disconnected {
+    fn abort(&self) {
+        if let Some(expiry) = &self.expiry {
+            expiry.abort();
+        }
+    }
+}
 
 /// A player for a guild.
 pub struct Player {
@@ -32,10 +77,46 @@ pub struct Player {
     pub driver: Driver,
     /// Queue of tracks.
     pub queue: Queue,
+    /// Rules the tracks enqueued by clients must follow.
+    pub policy: QueuePolicy,
+    /// Tracks played before the current one.
+    pub history: History,
+    /// Sounds playing on top of the queue.
+    pub overlays: Vec<Overlay>,
     /// Current volume of the player.
     pub volume: f32,
     /// Whether if the player is paused.
     pub paused: bool,
+    /// Whether to enqueue tracks related to the last played one when the queue runs out of
+    /// tracks.
+    pub autoplay: bool,
+    /// Provides the tracks enqueued by autoplay.
+    pub recommender: Arc<dyn Recommender>,
+    /// Whether if autoplay is fetching tracks, see [`Player::autoplay`].
+    fetching_related: bool,
+    /// Stops the playback after some time or tracks.
+    pub sleep_timer: Option<ActiveSleepTimer>,
+    /// Task that pauses the tracks once they have faded out.
+    pending_pause: Option<AbortHandle>,
+    /// Set after leaving the voice channel with [`Player::leave`], until connecting again.
+    pub disconnected: Option<Disconnected>,
+    /// Set while no track is playing or the player is paused, see [`Player::check_idle`].
+    idle: Option<Idle>,
+    /// Filters applied to all the tracks of the player.
+    pub filters: Arc<Filters>,
+    /// How the player moves between tracks.
+    pub transitions: Arc<Transitions>,
+    /// Receives the ids of the tracks that are about to end, taken by the [`handler`].
+    pub transition_receiver: Option<UnboundedReceiver<Uuid>>,
+    /// The mutex of this player, set by the [`handler`], locked by the tasks that resolve
+    /// the next track.
+    pub this: Weak<TicketedMutex<Player>>,
+    /// The session owning this player, used to leave the voice channel through
+    /// [`Playback::leave_player`](crate::playback::Playback::leave_player).
+    pub session: Weak<Session>,
+    /// Whether to play the next track once it is resolved, set when the current one ended
+    /// before that.
+    play_when_loaded: bool,
     pub sender: Sender,
     pub sources: SharedPtr<Sources>
 }
@@ -45,13 +126,31 @@ unsafe impl Send for Player {}
 impl Player {
     pub fn new(guild_id: GuildId, sources: SharedPtr<Sources>, config: Config, sender: Sender) -> Self {
         metrics().active_players.inc();
+        let (transitions, transition_receiver) = Transitions::new();
+
         Self {
             guild_id,
             channel_id: None,
             driver: Driver::new(config),
             queue: Queue::new(),
+            policy: QueuePolicy::default(),
+            history: History::default(),
+            overlays: Vec::new(),
             volume: 1.0,
             paused: false,
+            autoplay: false,
+            recommender: Arc::new(sources.clone()),
+            fetching_related: false,
+            sleep_timer: None,
+            pending_pause: None,
+            disconnected: None,
+            idle: None,
+            filters: Arc::new(Filters::new()),
+            transitions: Arc::new(transitions),
+            transition_receiver: Some(transition_receiver),
+            this: Weak::new(),
+            session: Weak::new(),
+            play_when_loaded: false,
             sender,
             sources
         }
@@ -66,7 +165,7 @@ impl Player {
         T: Into<Input>
     {
         if self.queue.is_repeat_enabled() {
-            self.queue.backup.push_front(source.clone());
+            self.queue.backup.push_front(PendingTrack::from_metadata(&meta, source.clone()));
         }
 
         self.queue.pause();
@@ -88,10 +187,115 @@ impl Player {
         }
     }
 
+    /// Enqueues the provided track without resolving it, it is resolved once it is about to
+    /// become the next track. Returns the track sent to clients until then.
+    pub async fn enqueue_pending(&mut self, pending: PendingTrack) -> TrackModel {
+        let track = pending.track.clone();
+
+        if self.queue.is_repeat_enabled() {
+            self.queue.backup.push_back(pending.clone());
+        }
+
+        if self.queue.enqueue_pending(pending) {
+            self.play_load_next().await;
+        } else {
+            self.load_next().await;
+        }
+
+        track
+    }
+
+    /// Inserts the provided track at the given index of the upcoming tracks without resolving
+    /// it, starting playback if nothing is playing. Returns the track sent to clients until
+    /// it is resolved.
+    pub async fn insert(&mut self, index: usize, pending: PendingTrack) -> TrackModel {
+        if self.queue.should_play() {
+            return self.enqueue_pending(pending).await;
+        }
+
+        let track = pending.track.clone();
+
+        if self.queue.is_repeat_enabled() {
+            self.queue.backup.push_back(pending.clone());
+        }
+
+        self.queue.insert(index, QueueEntry::Pending(pending));
+        self.load_next().await;
+
+        track
+    }
+
+    /// Removes the upcoming track with the given id from the queue.
+    pub async fn remove(&mut self, id: Uuid) -> Option<TrackModel> {
+        let entry = self.queue.remove(id)?;
+        entry.stop();
+
+        self.load_next().await;
+        Some(entry.track())
+    }
+
+    /// Plays a sound on top of the queue without touching it, ducking the music while it
+    /// plays if requested. The filters of the player are not applied to it.
+    pub fn play_overlay(&mut self, input: Input, meta: TrackMetadata, volume: f32, duck: Option<Ducking>) {
+        let meta = TrackMetadata {
+            overlay: true,
+            ..meta
+        };
+
+        let track = SongbirdTrack::new_with_uuid_and_data(input, meta.id, Arc::new(meta));
+        let handle = self.driver.play(track.volume(volume));
+
+        self.overlays.push(Overlay { handle, duck });
+
+        if let Some(duck) = duck {
+            self.duck(duck.fade());
+        }
+    }
+
+    /// Forgets an overlay that ended, restoring the volume of the music if it was the last
+    /// one ducking it. Returns whether if the track was an overlay.
+    pub fn overlay_ended(&mut self, id: Uuid) -> bool {
+        let Some(index) = self.overlays.iter().position(|o| o.handle.uuid() == id) else {
+            return false;
+        };
+
+        if let Some(duck) = self.overlays.swap_remove(index).duck {
+            self.duck(duck.fade());
+        }
+
+        true
+    }
+
+    /// Stops all the overlays, they are forgotten once their end events arrive.
+    pub fn stop_overlays(&self) {
+        for overlay in &self.overlays {
+            let _ = overlay.handle.stop();
+        }
+    }
+
+    /// Ducks the music to the lowest volume requested by the playing overlays, or restores
+    /// it if none of them ducks it.
+    fn duck(&self, fade: Duration) {
+        let gain = self.overlays.iter()
+            .filter_map(|o| o.duck)
+            .map(|d| d.gain())
+            .fold(1.0, f32::min);
+
+        self.filters.set_duck(gain, fade);
+    }
+
     /// Submits the provided input to the call driver, getting a [`TrackHandle`] and
     /// inserting the track data.
     async fn get_handle<T: Into<Input>>(&mut self, item: T, data: TrackMetadata) -> TrackHandle {
-        let track = SongbirdTrack::new_with_data(item.into(), Arc::new(data)).volume(self.volume);
+        let uuid = data.id;
+        let input = FilteredInput::wrap(
+            item.into(),
+            Arc::clone(&self.filters),
+            Arc::clone(&self.transitions),
+            uuid,
+            &data
+        );
+        let track = SongbirdTrack::new_with_uuid_and_data(input, uuid, Arc::new(data));
         let handle = self.driver.play(track.pause());
 
         handle
@@ -102,7 +306,7 @@ impl Player {
         T: Into<Input>
     {
         if self.queue.is_repeat_enabled() {
-            self.queue.backup.push_back(source.clone());
+            self.queue.backup.push_back(PendingTrack::from_metadata(&meta, source.clone()));
         }
 
         let handle = self.get_handle(item, meta).await;
@@ -112,17 +316,79 @@ impl Player {
     pub async fn update(&mut self, info: Option<ConnectionInfo>) -> Result<(), ConnectionError> {
         if let Some(info) = info {
             // The handler will update the channel field when events occur, so don't update it here
-            self.driver.connect(info).await
+            self.driver.connect(info).await?;
+
+            if let Some(disconnected) = self.disconnected.take() {
+                disconnected.abort();
+
+                if disconnected.resume {
+                    self.resume(Duration::ZERO).await;
+                }
+            }
+
+            Ok(())
         } else {
             self.driver.leave();
             Ok(())
         }
     }
 
+    /// Leaves the voice channel keeping the queue, position and filters, the playback is
+    /// paused until connecting again. `expiry` destroys the player if it doesn't connect
+    /// again in time.
+    pub fn leave(&mut self, expiry: Option<AbortHandle>) {
+        let resume = match self.disconnected.take() {
+            // Already left, keep whether to resume from the first time.
+            Some(previous) => {
+                previous.abort();
+                previous.resume
+            },
+            None => {
+                let resume = !self.paused;
+                self.pause(Duration::ZERO);
+                resume
+            }
+        };
+
+        self.disconnected = Some(Disconnected {
+            resume,
+            expiry
+        });
+
+        self.driver.leave();
+    }
+
+    /// Updates how long the player has been idle, without a track playing or paused. Returns
+    /// the time it has been idle once it reaches `timeout`, only once per idle period.
+    pub fn check_idle(&mut self, timeout: Duration) -> Option<Duration> {
+        if !self.paused && self.queue.current.is_some() {
+            self.idle = None;
+            return None;
+        }
+
+        let idle = self.idle.get_or_insert_with(|| Idle {
+            since: Instant::now(),
+            notified: false
+        });
+
+        let elapsed = idle.since.elapsed();
+        if idle.notified || elapsed < timeout {
+            return None;
+        }
+
+        idle.notified = true;
+        Some(elapsed)
+    }
+
     /// Destroys the player.
     #[instrument]
     pub async fn destroy(&mut self) -> Result<(), ConnectionError> {
         info!("Destroying player");
+
+        if let Some(disconnected) = self.disconnected.take() {
+            disconnected.abort();
+        }
+
         self.update(None).await?;
         self.queue.clear();
         self.driver.remove_all_global_events();
@@ -131,21 +397,167 @@ impl Player {
         Ok(())
     }
 
-    /// Pauses the currently playing track if available.
-    pub fn pause(&mut self) {
-        self.queue.pause();
+    /// Pauses the currently playing track if available, fading it out over `fade` first.
+    pub fn pause(&mut self, fade: Duration) {
         self.paused = true;
+
+        if let Some(pause) = self.pending_pause.take() {
+            pause.abort();
+        }
+
+        if fade.is_zero() {
+            self.queue.pause();
+            return;
+        }
+
+        let tracks = self.queue.playing().cloned().collect::<Vec<_>>();
+
+        for track in tracks.iter() {
+            track.data::<TrackMetadata>().fade.fade_to(0.0, fade);
+        }
+
+        self.pending_pause = Some(tokio::spawn(async move {
+            tokio::time::sleep(fade).await;
+
+            for track in tracks {
+                let _ = track.pause();
+            }
+        }).abort_handle());
     }
 
-    /// Resumes the currently playing track if available.
-    pub fn resume(&mut self) {
+    /// Resumes the currently playing track if available, fading it in over `fade`. If the
+    /// playback was stopped by the sleep timer, the next track is played instead.
+    pub async fn resume(&mut self, fade: Duration) {
+        if self.queue.current.is_none() && !self.queue.should_play() {
+            self.paused = false;
+            self.play_next().await;
+            return;
+        }
+
+        // If the tracks are still fading out, fade back in from where they are instead of
+        // from silence.
+        let fading_out = self.pending_pause.take().is_some_and(|pause| {
+            let running = !pause.is_finished();
+            pause.abort();
+            running
+        });
+
+        if self.paused {
+            if let Some(current) = self.queue.current() {
+                let meta = current.data::<TrackMetadata>();
+
+                if fading_out {
+                    meta.fade.fade_to(1.0, fade);
+                } else {
+                    meta.fade.fade_in(fade);
+                }
+            }
+        }
+
         self.queue.resume();
         self.paused = false;
     }
 
-    /// Changes the volume of the player.
-    pub fn set_volume(&mut self, volume: f32) {
-        self.queue.set_volume(volume);
+    /// Skips the currently playing track, fading it out over `fade` before stopping it.
+    pub fn skip(&mut self, fade: Duration) -> Option<TrackResult<TrackHandle>> {
+        if fade.is_zero() {
+            return self.queue.skip();
+        }
+
+        let current = self.queue.current()?.clone();
+        current.data::<TrackMetadata>().fade.fade_to(0.0, fade);
+
+        let track = current.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(fade).await;
+            let _ = track.stop();
+        });
+
+        Some(Ok(current))
+    }
+
+    /// Stops the upcoming tracks before the provided index without playing them, so skipping
+    /// the current track plays the one at that index. Returns whether if the index exists.
+    pub fn discard_before(&mut self, index: usize) -> bool {
+        let Some(discarded) = self.queue.take_before(index) else {
+            return false;
+        };
+
+        // The tracks were never played, so their end events are ignored by the handler.
+        for entry in discarded {
+            entry.stop();
+        }
+
+        true
+    }
+
+    /// Starts a sleep timer, replacing the previous one. `player` is the mutex of this player,
+    /// locked to stop the playback once the duration of the timer has elapsed.
+    pub fn set_sleep_timer(&mut self, timer: SleepTimer, player: Weak<TicketedMutex<Player>>) {
+        let task = timer.duration().map(|duration| tokio::spawn(async move {
+            tokio::time::sleep(duration).await;
+
+            if let Some(player) = player.upgrade() {
+                player.lock().await.fire_sleep_timer().await;
+            }
+        }).abort_handle());
+
+        self.sleep_timer = Some(ActiveSleepTimer::new(timer, task));
+    }
+
+    /// Stops the current track, and clears the queue or leaves the voice channel if the sleep
+    /// timer was set to.
+    async fn fire_sleep_timer(&mut self) {
+        let Some(active) = self.sleep_timer.take() else {
+            return;
+        };
+
+        info!("Sleep timer fired");
+        let timer = active.timer();
+        self.play_when_loaded = false;
+
+        if let Some(current) = &self.queue.current {
+            self.history.push(current).await;
+        }
+
+        if timer.clear_queue {
+            self.queue.clear();
+        } else {
+            // Taken out before stopping them, so the handler doesn't play the next track.
+            let stopped = self.queue.current.take().into_iter()
+                .chain(self.queue.ending.take());
+
+            for track in stopped {
+                let _ = track.handle.stop();
+            }
+        }
+
+        if timer.disconnect {
+            // Paused so the playback doesn't start again when connecting again.
+            self.pause(Duration::ZERO);
+
+            // Left from a task, since leaving locks this player.
+            let session = self.session.clone();
+            let guild = self.guild_id;
+
+            tokio::spawn(async move {
+                if let Some(session) = session.upgrade() {
+                    session.playback.leave_player(guild, &session).await;
+                }
+            });
+        }
+
+        let _ = self.sender.send(Outgoing::Event {
+            guild_id: self.guild_id.0.get(),
+            event: OutgoingEvent::SleepTimer(timer)
+        });
+    }
+
+    /// Changes the volume of the player, ramping to it over `ramp`.
+    pub fn set_volume(&mut self, volume: f32, ramp: Duration) {
+        // The volume is applied by the filter chain instead of songbird, so the compressor
+        // and limiter filters can act on it.
+        self.filters.set_volume(volume, ramp);
 
         self.volume = volume;
     }
@@ -154,11 +566,14 @@ impl Player {
         self.queue.set_repeat(repeat_mode).await;
 
         if self.queue.should_repeat_now() {
-            self.repeat_queue().await;
+            self.repeat_queue();
+            self.load_next().await;
         }
     }
 
-    async fn repeat_queue(&mut self) {
+    /// Enqueues the tracks of the backup again, they are resolved once they are about to
+    /// play.
+    fn repeat_queue(&mut self) {
         if let RepeatMode::Finite(reps) = &mut self.queue.repeat {
             *reps = reps.saturating_sub(1);
 
@@ -169,41 +584,284 @@ impl Player {
 
         let backup = self.queue.backup.drain(..).collect::<VecDeque<_>>();
 
-        for mut source in backup {
-            let playable = match self.sources.playable_for(&mut source).await {
-                Ok(p) => p,
+        for entry in backup {
+            // Keeps the metadata and user data of the previous entry until it is resolved.
+            let pending = PendingTrack::with_track(entry.source, entry.track);
+            self.queue.backup.push_back(pending.clone());
+            self.queue.rest.push_back(QueueEntry::Pending(pending));
+        }
+    }
+
+    /// Resolves the source of a pending track without holding the lock of the player, the
+    /// result is handled by [`Player::finish_loading`].
+    fn spawn_resolve(&self, mut pending: PendingTrack) {
+        let player = self.this.clone();
+        let sources = self.sources.clone();
+
+        tokio::spawn(async move {
+            let playable = sources.playable_for(&mut pending.source).await;
+
+            if let Some(player) = player.upgrade() {
+                player.lock().await.finish_loading(pending, playable).await;
+            }
+        });
+    }
+
+    /// Makes a resolved track the next one if the queue didn't change while resolving it,
+    /// sending a `TrackErrored` event if it failed. Then plays it if the current track
+    /// already ended, or keeps loading the next track.
+    async fn finish_loading(&mut self, pending: PendingTrack, playable: Result<Playable, IntoResponseError>) {
+        if self.queue.loading != Some(pending.id) {
+            return;
+        }
+
+        self.queue.loading = None;
+
+        let unchanged = self.queue.next.is_none() && matches!(
+            self.queue.rest.front(),
+            Some(QueueEntry::Pending(front)) if front.id == pending.id
+        );
+
+        // Otherwise, the entry that is first now is loaded instead.
+        if unchanged {
+            self.queue.rest.pop_front();
+
+            match playable {
+                Ok(playable) => {
+                    let next = self.resolved_handle(pending, playable).await;
+                    self.queue.set_next(next);
+                },
                 Err(e) => {
                     let _ = self.sender.send(Outgoing::Event {
                         guild_id: self.guild_id.0.get(),
                         event: OutgoingEvent::TrackErrored {
                             error: e.msg,
-                            track: if source.is_link() {
-                                Track::default()
-                            } else {
-                                source.track().unwrap_or_default()
-                            }
+                            track: pending.track
                         }
                     });
+                }
+            }
+        }
+
+        if std::mem::take(&mut self.play_when_loaded) && self.queue.current.is_none() {
+            self.play_next().await;
+        } else {
+            self.load_next().await;
+        }
+    }
+
+    /// Submits a resolved pending track to the driver, keeping its id and track data.
+    async fn resolved_handle(&mut self, pending: PendingTrack, playable: Playable) -> HandleWithSource {
+        let PendingTrack { id, source, track } = pending;
+
+        let handle = self.get_handle(playable.input, TrackMetadata {
+            id,
+            loudness: playable.loudness,
+            user_data: track.user_data,
+            start_time: track.start_time.map(Duration::from_millis),
+            end_time: track.end_time.map(Duration::from_millis),
+            ..TrackMetadata::new(playable.meta, self.guild_id.0.get())
+        }).await;
 
-                    continue;
+        HandleWithSource::new(handle, source.into())
+    }
+
+    /// Fetches tracks related to the last played one in the background if autoplay is enabled,
+    /// without holding the lock of the player. They are enqueued by
+    /// [`Player::finish_autoplay`], returns whether if they are being fetched.
+    fn autoplay(&mut self) -> bool {
+        if !self.autoplay {
+            return false;
+        }
+
+        if self.fetching_related {
+            return true;
+        }
+
+        let Some(last) = self.history.last() else {
+            return false;
+        };
+
+        let source = last.source.clone();
+        let recommender = Arc::clone(&self.recommender);
+        let player = self.this.clone();
+        self.fetching_related = true;
+
+        tokio::spawn(async move {
+            let tracks = match recommender.related(&source).await {
+                Ok(tracks) => tracks.unwrap_or_default(),
+                Err(e) => {
+                    warn!("Failed to get tracks to autoplay: {e}");
+                    Vec::new()
                 }
             };
 
-            self.enqueue_inner(playable.input, TrackMetadata {
-                guild: self.guild_id.0.get(),
-                metadata: playable.meta
-            }, source).await;
+            if let Some(player) = player.upgrade() {
+                player.lock().await.finish_autoplay(tracks).await;
+            }
+        });
+
+        true
+    }
+
+    /// Enqueues the tracks fetched by autoplay that weren't played already, playing them if
+    /// the queue ran out of tracks while fetching them.
+    async fn finish_autoplay(&mut self, tracks: Vec<ListedTrack>) {
+        self.fetching_related = false;
+        let play = std::mem::take(&mut self.play_when_loaded) && self.queue.should_play();
+
+        // Disabled while fetching.
+        if !self.autoplay {
+            return;
+        }
+
+        // Skip what was already played.
+        let played = self.history.tracks()
+            .filter_map(|t| t.source_url.as_deref())
+            .collect::<HashSet<_>>();
+
+        let tracks = tracks.into_iter()
+            .filter(|t| !t.track.source_url.as_deref().is_some_and(|url| played.contains(url)))
+            .take(AUTOPLAY_TRACKS)
+            .collect::<Vec<_>>();
+
+        if tracks.is_empty() {
+            return;
+        }
+
+        if play {
+            metrics().playing_players.inc();
+        }
+
+        let mut enqueued = Vec::with_capacity(tracks.len());
+
+        for ListedTrack { source, track } in tracks {
+            let pending = PendingTrack::with_track(source, track);
+            enqueued.push(pending.track.clone());
+            self.queue.rest.push_back(QueueEntry::Pending(pending));
+        }
+
+        let _ = self.sender.send(Outgoing::Event {
+            guild_id: self.guild_id.0.get(),
+            event: OutgoingEvent::Autoplay {
+                tracks: enqueued
+            }
+        });
+
+        if play {
+            self.play_next().await;
+        } else {
+            self.load_next().await;
+        }
+    }
+
+    /// Fills the `next` slot of the queue if it is empty. Resolved entries become the next
+    /// track right away, while pending ones are resolved in the background, see
+    /// [`Player::spawn_resolve`]. Returns whether if there is a next track or one is loading.
+    pub async fn load_next(&mut self) -> bool {
+        if self.queue.next.is_some() || self.queue.loading.is_some() {
+            return true;
         }
+
+        match self.queue.take_next_entry() {
+            Some(QueueEntry::Resolved(track)) => self.queue.set_next(track),
+            Some(QueueEntry::Pending(pending)) => {
+                // Kept in queue while loading, so it is still listed and can be edited.
+                self.queue.loading = Some(pending.id);
+                self.queue.rest.push_front(QueueEntry::Pending(pending.clone()));
+                self.spawn_resolve(pending);
+            },
+            None => return false
+        }
+
+        true
+    }
+
+    /// Starts playing the next track before the current one ends, as configured in the
+    /// [`Transitions`] of the player. `track` is the id of the track that is about to end.
+    pub async fn start_transition(&mut self, track: Uuid) {
+        if self.queue.current().map(|h| h.uuid()) != Some(track) || self.queue.next.is_none() {
+            return;
+        }
+
+        // A looping track plays again instead of ending, its source tells again when the
+        // last loop is about to end.
+        if let Some(current) = self.queue.current() {
+            let looping = current.get_info().await
+                .is_ok_and(|info| matches!(info.loops, LoopState::Infinite | LoopState::Finite(1..)));
+
+            if looping {
+                return;
+            }
+        }
+
+        if let Some(timer) = &mut self.sleep_timer {
+            // Let the track end, so the sleep timer stops the playback right after it.
+            if timer.is_last_track() {
+                return;
+            }
+
+            timer.track_ended();
+        }
+
+        let fade = self.transitions.get().fade();
+
+        if let Some(current) = self.queue.current.take() {
+            self.history.push(&current).await;
+
+            if !fade.is_zero() {
+                current.handle.data::<TrackMetadata>().fade.fade_to(0.0, fade);
+            }
+
+            // keep the track so it can be stopped or paused while it finishes playing.
+            self.queue.ending = Some(current);
+        }
+
+        if let Some(next) = self.queue.next() {
+            if !fade.is_zero() {
+                next.data::<TrackMetadata>().fade.fade_in(fade);
+            }
+        }
+
+        self.play_next().await;
     }
 
     pub async fn play_load_next(&mut self) {
         // take the track that finished playing.
-        self.queue.current.take();
+        if let Some(finished) = self.queue.current.take() {
+            if self.sleep_timer.as_mut().is_some_and(ActiveSleepTimer::track_ended) {
+                // Put it back, so the sleep timer stops it like a playing track.
+                self.queue.current = Some(finished);
+                self.fire_sleep_timer().await;
+                return;
+            }
+
+            self.history.push(&finished).await;
+        }
+
+        self.play_next().await;
+    }
+
+    async fn play_next(&mut self) {
+        // The next track may not be resolved yet if the queue was edited, or it failed to.
+        self.load_next().await;
 
         if self.queue.should_play() {
             // if true here, we're empty of tracks.
             info!("Queue empty");
             metrics().playing_players.dec();
+
+            // Played once fetched, by `finish_autoplay`.
+            if self.autoplay() {
+                self.play_when_loaded = true;
+            }
+
+            return;
+        }
+
+        if self.queue.next.is_none() {
+            // Played once resolved, by `finish_loading`.
+            self.play_when_loaded = true;
             return;
         }
 
@@ -219,25 +877,50 @@ impl Player {
             }
 
             if self.queue.should_repeat_now() {
-                self.repeat_queue().await;
+                self.repeat_queue();
             }
 
-            if !self.queue.load_next() {
+            if !self.load_next().await {
                 warn!("Queue finished after having an error playing a track");
                 self.queue.current.take();
                 return;
             }
+
+            if self.queue.next.is_none() {
+                self.queue.current.take();
+                self.play_when_loaded = true;
+                return;
+            }
+        }
+
+        // The previous track ended while fading out to pause, so keep the player paused.
+        if self.paused && self.pending_pause.as_ref().is_some_and(|pause| !pause.is_finished()) {
+            self.queue.pause();
         }
 
         // If we're here, we succeeded on playing, so load next one if available
         if self.queue.should_repeat_now() {
-            self.repeat_queue().await;
+            self.repeat_queue();
         }
 
-        self.queue.load_next();
+        self.load_next().await;
+    }
+
+    /// Upcoming tracks starting at `offset`, up to `limit` of them.
+    pub fn queue_page(&self, offset: usize, limit: usize) -> QueuePage {
+        QueuePage {
+            total: self.queue.upcoming_len(),
+            offset,
+            tracks: self.queue.upcoming_tracks()
+                .skip(offset)
+                .take(limit)
+                .collect()
+        }
     }
 
-    pub fn as_json(&self) -> PlayerModel {
+    /// Serializable state of the player, `queue` sets whether if the upcoming tracks are
+    /// included.
+    pub fn as_json(&self, queue: bool) -> PlayerModel {
         fn track(handle: &TrackHandle) -> TrackModel {
             handle.data::<TrackMetadata>().track()
         }
@@ -247,20 +930,18 @@ impl Player {
             channel_id: self.channel_id.map(|c| c.0),
             paused: self.paused,
             volume: (self.volume * 100.0) as _,
+            filters: self.filters.get(),
+            normalization: self.filters.normalization(),
+            transition: self.transitions.get(),
+            shuffle: self.queue.shuffle,
+            autoplay: self.autoplay,
+            sleep_timer: self.sleep_timer.as_ref().map(ActiveSleepTimer::remaining),
+            queue_policy: self.policy.clone(),
             currently_playing: self.queue.current().map(track),
-            queue: {
-                let mut v = Vec::new();
-
-                if let Some(next) = self.queue.next().map(track) {
-                    v.push(next);
-                }
-
-                v.extend(self.queue.rest.iter()
-                    .map(|t| track(&t.handle)));
-
-                v
-            }
-
+            position: self.queue.current()
+                .map(|handle| handle.data::<TrackMetadata>().position.get().as_millis()),
+            queue_length: self.queue.upcoming_len(),
+            queue: queue.then(|| self.queue.upcoming_tracks().collect())
         }
     }
 }
@@ -0,0 +1,48 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use songbird::tracks::TrackHandle;
+
+/// Maximum duck fade duration in milliseconds.
+const MAX_FADE: u64 = 10_000;
+
+fn default_fade() -> u64 {
+    300
+}
+
+/// Lowers the volume of the music while an overlay plays.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug)]
+pub struct Ducking {
+    /// Volume of the music while ducked, in percent of the volume of the player.
+    pub volume: u8,
+    /// Duration in milliseconds of the fade to the ducked volume and back.
+    #[serde(default = "default_fade")]
+    pub fade: u64
+}
+
+impl Ducking {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.volume > 100 {
+            return Err("Ducked volume must be between 0 and 100");
+        }
+
+        if self.fade > MAX_FADE {
+            return Err("Duck fade duration must be between 0 and 10000ms");
+        }
+
+        Ok(())
+    }
+
+    pub fn gain(&self) -> f32 {
+        self.volume as f32 / 100.0
+    }
+
+    pub fn fade(&self) -> Duration {
+        Duration::from_millis(self.fade)
+    }
+}
+
+/// A sound playing on top of the queue.
+pub struct Overlay {
+    pub handle: TrackHandle,
+    pub duck: Option<Ducking>
+}
@@ -0,0 +1,296 @@
+use std::collections::HashSet;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::api::error::IntoResponseError;
+use crate::api::model::play::PlaySource;
+use crate::playback::player::queue::Queue;
+use crate::source::ListedTrack;
+
+fn default_requester_key() -> String {
+    "requester".to_string()
+}
+
+/// Rules the tracks enqueued in a player must follow, every rule is disabled by default.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct QueuePolicy {
+    /// Whether to reject tracks whose link is already in the queue, including the current track.
+    #[serde(default)]
+    pub deduplicate: bool,
+    /// Maximum number of upcoming tracks.
+    #[serde(default)]
+    pub max_length: Option<usize>,
+    /// Maximum number of upcoming tracks requested by the same requester.
+    #[serde(default)]
+    pub max_per_requester: Option<usize>,
+    /// Key of the `user_data` of the tracks that identifies who requested them, tracks without
+    /// it are not limited per requester.
+    #[serde(default = "default_requester_key")]
+    pub requester_key: String
+}
+
+impl Default for QueuePolicy {
+    fn default() -> Self {
+        Self {
+            deduplicate: false,
+            max_length: None,
+            max_per_requester: None,
+            requester_key: default_requester_key()
+        }
+    }
+}
+
+/// A rule of a [`QueuePolicy`] broken by a play request.
+#[derive(Debug, Clone, Copy)]
+pub enum PolicyViolation {
+    Duplicate,
+    QueueFull,
+    RequesterLimit
+}
+
+impl From<PolicyViolation> for IntoResponseError {
+    fn from(value: PolicyViolation) -> Self {
+        let (msg, code) = match value {
+            PolicyViolation::Duplicate => ("The track is already in queue", "duplicate_track"),
+            PolicyViolation::QueueFull => ("The queue is full", "queue_full"),
+            PolicyViolation::RequesterLimit => (
+                "The requester has reached the maximum number of queued tracks",
+                "requester_limit"
+            )
+        };
+
+        IntoResponseError::new(msg)
+            .with_status(StatusCode::CONFLICT)
+            .with_code(code)
+    }
+}
+
+impl QueuePolicy {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.max_length == Some(0) || self.max_per_requester == Some(0) {
+            return Err("Queue limits must be greater than 0");
+        }
+
+        if self.requester_key.is_empty() {
+            return Err("Requester key must not be empty");
+        }
+
+        Ok(())
+    }
+
+    /// Checks a single track with the provided source and user data can be enqueued.
+    pub fn check(
+        &self,
+        queue: &Queue,
+        source: &PlaySource,
+        user_data: Option<&Value>
+    ) -> Result<(), PolicyViolation> {
+        if self.deduplicate {
+            if let Some(link) = source.link() {
+                if queue.links().any(|queued| queued == link) {
+                    return Err(PolicyViolation::Duplicate);
+                }
+            }
+        }
+
+        self.check_limits(queue, 1, user_data)
+    }
+
+    /// Leaves out the tracks of a list that are already in queue or repeated in the list, then
+    /// checks the remaining ones can be enqueued together.
+    pub fn filter(
+        &self,
+        queue: &Queue,
+        tracks: Vec<ListedTrack>,
+        user_data: Option<&Value>
+    ) -> Result<Vec<ListedTrack>, PolicyViolation> {
+        let tracks = if self.deduplicate && !tracks.is_empty() {
+            let mut seen = queue.links()
+                .map(ToString::to_string)
+                .collect::<HashSet<_>>();
+
+            let tracks = tracks.into_iter()
+                .filter(|t| t.source.link().map_or(true, |link| seen.insert(link.to_string())))
+                .collect::<Vec<_>>();
+
+            if tracks.is_empty() {
+                return Err(PolicyViolation::Duplicate);
+            }
+
+            tracks
+        } else {
+            tracks
+        };
+
+        self.check_limits(queue, tracks.len(), user_data)?;
+        Ok(tracks)
+    }
+
+    /// Checks `count` more tracks with the provided user data fit in the queue.
+    fn check_limits(
+        &self,
+        queue: &Queue,
+        count: usize,
+        user_data: Option<&Value>
+    ) -> Result<(), PolicyViolation> {
+        if let Some(max) = self.max_length {
+            if queue.upcoming_len() + count > max {
+                return Err(PolicyViolation::QueueFull);
+            }
+        }
+
+        if let (Some(max), Some(requester)) = (self.max_per_requester, self.requester(user_data)) {
+            let queued = queue.upcoming_tracks()
+                .filter(|t| self.requester(t.user_data.as_ref()) == Some(requester))
+                .count();
+
+            if queued + count > max {
+                return Err(PolicyViolation::RequesterLimit);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn requester<'a>(&self, user_data: Option<&'a Value>) -> Option<&'a Value> {
+        user_data?.get(&self.requester_key).filter(|r| !r.is_null())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::playback::handle::{PendingTrack, QueueEntry};
+    use super::*;
+
+    fn source(link: &str) -> PlaySource {
+        PlaySource::Link {
+            force_ytdlp: false,
+            link: link.to_string()
+        }
+    }
+
+    fn listed(links: &[&str]) -> Vec<ListedTrack> {
+        links.iter().map(|link| source(link).into()).collect()
+    }
+
+    /// A queue with pending tracks of the provided links, requested by `requester`.
+    fn queue(links: &[&str], requester: &str) -> Queue {
+        let mut queue = Queue::new();
+
+        for link in links {
+            let mut pending = PendingTrack::new(source(link));
+            pending.track.user_data = Some(json!({ "requester": requester }));
+            queue.rest.push_back(QueueEntry::Pending(pending));
+        }
+
+        queue
+    }
+
+    #[test]
+    fn duplicates_are_only_rejected_when_deduplicating() {
+        let queue = queue(&["a"], "alice");
+        let mut policy = QueuePolicy::default();
+
+        assert!(policy.check(&queue, &source("a"), None).is_ok());
+
+        policy.deduplicate = true;
+        assert!(matches!(policy.check(&queue, &source("a"), None), Err(PolicyViolation::Duplicate)));
+        assert!(policy.check(&queue, &source("b"), None).is_ok());
+    }
+
+    #[test]
+    fn full_queues_reject_tracks() {
+        let queue = queue(&["a", "b"], "alice");
+        let policy = QueuePolicy {
+            max_length: Some(2),
+            ..Default::default()
+        };
+
+        assert!(matches!(policy.check(&queue, &source("c"), None), Err(PolicyViolation::QueueFull)));
+        assert!(QueuePolicy { max_length: Some(3), ..policy }.check(&queue, &source("c"), None).is_ok());
+    }
+
+    #[test]
+    fn requesters_are_limited_separately() {
+        let queue = queue(&["a", "b"], "alice");
+        let policy = QueuePolicy {
+            max_per_requester: Some(2),
+            ..Default::default()
+        };
+
+        let alice = json!({ "requester": "alice" });
+        let bob = json!({ "requester": "bob" });
+        let nobody = json!({ "requester": null });
+
+        assert!(matches!(policy.check(&queue, &source("c"), Some(&alice)), Err(PolicyViolation::RequesterLimit)));
+        assert!(policy.check(&queue, &source("c"), Some(&bob)).is_ok());
+        assert!(policy.check(&queue, &source("c"), Some(&nobody)).is_ok());
+        assert!(policy.check(&queue, &source("c"), None).is_ok());
+    }
+
+    #[test]
+    fn requester_key_is_configurable() {
+        let queue = queue(&["a"], "alice");
+        let policy = QueuePolicy {
+            max_per_requester: Some(1),
+            ..Default::default()
+        };
+
+        let alice = json!({ "requester": "alice" });
+        assert!(matches!(policy.check(&queue, &source("b"), Some(&alice)), Err(PolicyViolation::RequesterLimit)));
+
+        // The tracks in queue don't have a `user`, so they don't count.
+        let policy = QueuePolicy {
+            requester_key: "user".to_string(),
+            ..policy
+        };
+
+        assert!(policy.check(&queue, &source("b"), Some(&json!({ "user": "alice" }))).is_ok());
+    }
+
+    #[test]
+    fn lists_leave_out_queued_and_repeated_tracks() {
+        let queue = queue(&["a"], "alice");
+        let policy = QueuePolicy {
+            deduplicate: true,
+            ..Default::default()
+        };
+
+        let tracks = policy.filter(&queue, listed(&["a", "b", "c", "b"]), None).unwrap();
+        let links = tracks.iter().filter_map(|t| t.source.link()).collect::<Vec<_>>();
+        assert_eq!(links, ["b", "c"]);
+
+        assert!(matches!(policy.filter(&queue, listed(&["a", "a"]), None), Err(PolicyViolation::Duplicate)));
+    }
+
+    #[test]
+    fn lists_must_fit_in_the_queue() {
+        let queue = queue(&["a"], "alice");
+        let policy = QueuePolicy {
+            deduplicate: true,
+            max_length: Some(3),
+            ..Default::default()
+        };
+
+        assert_eq!(policy.filter(&queue, listed(&["a", "b", "c"]), None).map(|t| t.len()).ok(), Some(2));
+        assert!(matches!(policy.filter(&queue, listed(&["b", "c", "d"]), None), Err(PolicyViolation::QueueFull)));
+
+        let policy = QueuePolicy {
+            max_per_requester: Some(2),
+            ..Default::default()
+        };
+
+        let alice = json!({ "requester": "alice" });
+        assert!(matches!(policy.filter(&queue, listed(&["b", "c"]), Some(&alice)), Err(PolicyViolation::RequesterLimit)));
+        assert!(policy.filter(&queue, listed(&["b"]), Some(&alice)).is_ok());
+    }
+
+    #[test]
+    fn limits_must_be_positive() {
+        assert!(QueuePolicy::default().validate().is_ok());
+        assert!(QueuePolicy { max_length: Some(0), ..Default::default() }.validate().is_err());
+        assert!(QueuePolicy { max_per_requester: Some(0), ..Default::default() }.validate().is_err());
+        assert!(QueuePolicy { requester_key: String::new(), ..Default::default() }.validate().is_err());
+    }
+}
@@ -1,8 +1,12 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+use rand::Rng;
+use rand::seq::SliceRandom;
 use songbird::tracks::{TrackHandle, TrackResult};
-use crate::api::model::play::PlaySource;
+use uuid::Uuid;
+use crate::api::model::track::Track;
 use crate::metrics::metrics;
-use crate::playback::handle::HandleWithSource;
+use crate::playback::handle::{HandleWithSource, PendingTrack, QueueEntry};
+use crate::playback::metadata::TrackMetadata;
 
 #[derive(Default)]
 pub enum RepeatMode {
@@ -19,10 +23,18 @@ pub struct Queue {
     pub current: Option<HandleWithSource>,
     /// The next track in queue.
     pub next: Option<HandleWithSource>,
-    /// The rest of the queue.
-    pub rest: VecDeque<HandleWithSource>,
-    pub backup: VecDeque<PlaySource>,
+    /// The rest of the queue, tracks are resolved once they become the next one.
+    pub rest: VecDeque<QueueEntry>,
+    /// Id of the pending entry at the front of `rest` while it is resolved to become the
+    /// next track.
+    pub loading: Option<Uuid>,
+    /// The previous track while it fades out, after a transition started the current one.
+    pub ending: Option<HandleWithSource>,
+    /// Tracks to enqueue again when repeating the queue, with the id of their current entry.
+    pub backup: VecDeque<PendingTrack>,
     pub repeat: RepeatMode,
+    /// Whether if the next track is picked randomly from the upcoming ones.
+    pub shuffle: bool
 }
 
 impl Queue {
@@ -31,8 +43,11 @@ impl Queue {
             current: None,
             next: None,
             rest: VecDeque::new(),
+            loading: None,
+            ending: None,
             backup: VecDeque::new(),
             repeat: RepeatMode::Off,
+            shuffle: false
         }
     }
 
@@ -49,26 +64,31 @@ impl Queue {
         self.next.as_ref().map(|i| &i.handle)
     }
 
+    /// The tracks that are being played, the current one and the previous one if it is
+    /// still fading out.
+    pub fn playing(&self) -> impl Iterator<Item = &TrackHandle> {
+        self.current().into_iter()
+            .chain(self.ending.as_ref().map(|t| &t.handle))
+    }
+
     pub fn pause(&self) {
-        self.current().map(|t| t.pause());
+        for track in self.playing() {
+            let _ = track.pause();
+        }
     }
 
     pub fn resume(&self) {
-        self.current().map(|t| t.play());
+        for track in self.playing() {
+            let _ = track.play();
+        }
     }
 
-    #[allow(unused)]
-    pub fn skip(&mut self) -> Option<TrackResult<TrackHandle>> {
-        let current = self.current.take()?;
+    pub fn skip(&self) -> Option<TrackResult<TrackHandle>> {
+        let current = self.current()?;
 
         // Stopping the current track triggers the TrackEnd event, so the
         // event handler will play the next one.
-        Some(current.handle.stop().map(|_| current.handle))
-    }
-
-    pub fn set_volume(&self, volume: f32) {
-        self.current.as_ref().map(|handle| handle.handle.set_volume(volume));
-        self.next.as_ref().map(|handle| handle.handle.set_volume(volume));
+        Some(current.stop().map(|_| current.clone()))
     }
 
     pub async fn set_repeat(&mut self, mode: RepeatMode) {
@@ -80,15 +100,15 @@ impl Queue {
             }
 
             if let Some(current) = self.current.as_ref() {
-                self.backup.push_back(current.full_source().await)
+                self.backup.push_back(current.to_pending().await)
             }
 
             if let Some(next) = self.next.as_ref() {
-                self.backup.push_back(next.full_source().await);
+                self.backup.push_back(next.to_pending().await);
             }
 
             for i in &self.rest {
-                self.backup.push_back(i.full_source().await);
+                self.backup.push_back(i.to_pending().await);
             }
         } else {
             self.backup.clear();
@@ -116,14 +136,22 @@ impl Queue {
         }
     }
 
-    pub fn load_next(&mut self) -> bool {
-        if let Some(next) = self.rest.pop_front() {
-            let _ = next.handle.make_playable();
-            self.next = Some(next);
-            true
+    /// Takes the entry that should become the next track out of the rest of the queue,
+    /// picked randomly if shuffle mode is enabled.
+    pub fn take_next_entry(&mut self) -> Option<QueueEntry> {
+        let index = if self.shuffle && !self.rest.is_empty() {
+            rand::thread_rng().gen_range(0..self.rest.len())
         } else {
-            false
-        }
+            0
+        };
+
+        self.rest.remove(index)
+    }
+
+    /// Sets the next track, preloading it.
+    pub fn set_next(&mut self, next: HandleWithSource) {
+        let _ = next.handle.make_playable();
+        self.next = Some(next);
     }
 
     pub fn enqueue(&mut self, track: HandleWithSource) -> bool {
@@ -132,22 +160,188 @@ impl Queue {
             metrics().playing_players.inc();
             true
         } else if self.next.is_none() && self.rest.is_empty() {
-            let _ = track.handle.make_playable();
-            self.next = Some(track);
+            self.set_next(track);
             false
         } else {
-            self.rest.push_back(track);
+            self.rest.push_back(QueueEntry::Resolved(track));
             false
         }
     }
 
+    /// Enqueues a track that is resolved later, returns whether if the queue was empty.
+    pub fn enqueue_pending(&mut self, track: PendingTrack) -> bool {
+        let play = self.should_play();
+
+        if play {
+            metrics().playing_players.inc();
+        }
+
+        self.rest.push_back(QueueEntry::Pending(track));
+        play
+    }
+
+    /// Number of tracks waiting to be played.
+    pub fn upcoming_len(&self) -> usize {
+        self.next.iter().len() + self.rest.len()
+    }
+
+    /// Ids of the tracks waiting to be played, starting with the preloaded `next` one.
+    pub fn upcoming_ids(&self) -> impl Iterator<Item = Uuid> + '_ {
+        self.next.iter()
+            .map(HandleWithSource::id)
+            .chain(self.rest.iter().map(QueueEntry::id))
+    }
+
+    /// Tracks waiting to be played, starting with the preloaded `next` one.
+    pub fn upcoming_tracks(&self) -> impl Iterator<Item = Track> + '_ {
+        self.next.iter()
+            .map(|t| t.handle.data::<TrackMetadata>().track())
+            .chain(self.rest.iter().map(QueueEntry::track))
+    }
+
+    /// Links of the sources of the current and upcoming tracks.
+    pub fn links(&self) -> impl Iterator<Item = &str> {
+        self.current.iter()
+            .chain(self.next.iter())
+            .filter_map(|t| t.source.link())
+            .chain(self.rest.iter().filter_map(QueueEntry::link))
+    }
+
+    /// Index of the entry with the given id among the upcoming tracks.
+    pub fn position(&self, id: Uuid) -> Option<usize> {
+        self.upcoming_ids().position(|entry| entry == id)
+    }
+
+    /// Edits the upcoming tracks as a single list, preloading the track that ends up first.
+    /// If that track isn't resolved yet, `next` is left empty for the player to resolve it.
+    fn edit<R>(&mut self, f: impl FnOnce(&mut VecDeque<QueueEntry>) -> R) -> R {
+        let next_id = self.next.as_ref().map(HandleWithSource::id);
+
+        if let Some(next) = self.next.take() {
+            self.rest.push_front(QueueEntry::Resolved(next));
+        }
+
+        let res = f(&mut self.rest);
+
+        if matches!(self.rest.front(), Some(QueueEntry::Resolved(_))) {
+            if let Some(QueueEntry::Resolved(next)) = self.rest.pop_front() {
+                if Some(next.id()) != next_id {
+                    let _ = next.handle.make_playable();
+                }
+
+                self.next = Some(next);
+            }
+        }
+
+        res
+    }
+
+    /// Reorders the entries of the backup that are still upcoming to match the queue.
+    fn sync_backup(&mut self) {
+        if !self.is_repeat_enabled() {
+            return;
+        }
+
+        let order = self.upcoming_ids().collect::<Vec<_>>();
+        let ids = order.iter().collect::<HashSet<_>>();
+        let slots = self.backup.iter()
+            .enumerate()
+            .filter(|(_, entry)| ids.contains(&entry.id))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+
+        let mut entries = slots.iter()
+            .map(|i| (self.backup[*i].id, self.backup[*i].clone()))
+            .collect::<HashMap<_, _>>();
+
+        let ordered = order.into_iter()
+            .filter_map(|id| entries.remove(&id));
+
+        for (slot, entry) in slots.into_iter().zip(ordered) {
+            self.backup[slot] = entry;
+        }
+    }
+
+    /// Removes the upcoming entry with the given id, the track is not stopped.
+    pub fn remove(&mut self, id: Uuid) -> Option<QueueEntry> {
+        let index = self.position(id)?;
+        self.backup.retain(|entry| entry.id != id);
+
+        self.edit(|tracks| tracks.remove(index))
+    }
+
+    /// Moves the upcoming entry with the given id to the provided index, or to the end of
+    /// the queue if the index is out of bounds. Returns whether if the entry was found.
+    pub fn move_to(&mut self, id: Uuid, index: usize) -> bool {
+        let Some(from) = self.position(id) else {
+            return false;
+        };
+
+        self.edit(|tracks| {
+            if let Some(track) = tracks.remove(from) {
+                tracks.insert(index.min(tracks.len()), track);
+            }
+        });
+
+        self.sync_backup();
+        true
+    }
+
+    /// Swaps two upcoming entries, returns whether if both were found.
+    pub fn swap(&mut self, first: Uuid, second: Uuid) -> bool {
+        let (Some(a), Some(b)) = (self.position(first), self.position(second)) else {
+            return false;
+        };
+
+        self.edit(|tracks| tracks.swap(a, b));
+        self.sync_backup();
+        true
+    }
+
+    /// Inserts an entry at the provided index of the upcoming tracks, or at the end of the
+    /// queue if the index is out of bounds.
+    pub fn insert(&mut self, index: usize, entry: QueueEntry) {
+        self.edit(|tracks| tracks.insert(index.min(tracks.len()), entry));
+        self.sync_backup();
+    }
+
+    /// Takes out the upcoming tracks before the provided index, so the track at that index
+    /// is the next one to play. Returns `None` if the index is out of bounds.
+    pub fn take_before(&mut self, index: usize) -> Option<Vec<QueueEntry>> {
+        if index >= self.upcoming_len() {
+            return None;
+        }
+
+        Some(self.edit(|tracks| tracks.drain(..index).collect()))
+    }
+
+    /// Shuffles the upcoming tracks once, the repeat backup keeps the original order.
+    pub fn shuffle(&mut self) {
+        self.edit(|tracks| tracks.make_contiguous().shuffle(&mut rand::thread_rng()));
+    }
+
+    /// Enables or disables picking the next track randomly, when enabled the preloaded
+    /// track is replaced by a random one right away.
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+
+        if shuffle {
+            self.edit(|tracks| {
+                if !tracks.is_empty() {
+                    let index = rand::thread_rng().gen_range(0..tracks.len());
+                    tracks.swap(0, index);
+                }
+            });
+        }
+    }
+
     pub fn force_track(&mut self, track: HandleWithSource) {
         if self.should_play() {
             metrics().playing_players.inc();
         }
 
         if let Some(next) = self.next.take() {
-            self.rest.push_front(next);
+            self.rest.push_front(QueueEntry::Resolved(next));
         }
 
         self.next = self.current.take();
@@ -160,14 +354,262 @@ impl Queue {
         }
 
         self.current.take().map(|t| t.handle.stop());
+        self.ending.take().map(|t| t.handle.stop());
         self.next.take().map(|t| t.handle.stop());
 
         for t in self.rest.drain(..) {
-            let _ = t.handle.stop();
+            t.stop();
         }
 
+        self.loading = None;
+
         for t in self.backup.drain(..) {
             drop(t);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use bytes::Bytes;
+    use songbird::{Config, Driver};
+    use songbird::input::Input;
+    use songbird::tracks::Track as SongbirdTrack;
+    use crate::api::model::play::PlaySource;
+    use crate::playback::handle::MinimalSource;
+    use super::*;
+
+    fn pending(link: &str) -> PendingTrack {
+        PendingTrack::new(PlaySource::Link {
+            force_ytdlp: false,
+            link: link.to_string()
+        })
+    }
+
+    /// A track submitted to the driver paused, like the tracks of the players.
+    fn resolved(driver: &mut Driver) -> HandleWithSource {
+        let meta = TrackMetadata::new(Default::default(), 1);
+        let track = SongbirdTrack::new_with_uuid_and_data(Input::from(Bytes::new()), meta.id, Arc::new(meta));
+
+        HandleWithSource::new(driver.play(track.pause()), MinimalSource::Bytes(Bytes::new()))
+    }
+
+    fn ids(queue: &Queue) -> Vec<Uuid> {
+        queue.upcoming_ids().collect()
+    }
+
+    /// The first upcoming entry must be preloaded in `next` whenever it is resolved.
+    fn assert_preloaded(queue: &Queue) {
+        if queue.next.is_none() {
+            assert!(!matches!(queue.rest.front(), Some(QueueEntry::Resolved(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn moving_a_pending_entry_first_empties_next() {
+        let mut driver = Driver::new(Config::default());
+        let mut queue = Queue::new();
+        let next = resolved(&mut driver);
+        let (first, second) = (pending("a"), pending("b"));
+        let expected = vec![second.id, next.id(), first.id];
+
+        queue.next = Some(next);
+        queue.rest.extend([QueueEntry::Pending(first), QueueEntry::Pending(second)]);
+
+        assert!(queue.move_to(expected[0], 0));
+        assert_eq!(ids(&queue), expected);
+        assert!(queue.next.is_none());
+    }
+
+    #[tokio::test]
+    async fn moving_a_resolved_entry_first_preloads_it() {
+        let mut driver = Driver::new(Config::default());
+        let mut queue = Queue::new();
+        let (next, moved) = (resolved(&mut driver), resolved(&mut driver));
+        let first = pending("a");
+        let expected = vec![moved.id(), next.id(), first.id];
+
+        queue.next = Some(next);
+        queue.rest.extend([QueueEntry::Pending(first), QueueEntry::Resolved(moved)]);
+
+        assert!(queue.move_to(expected[0], 0));
+        assert_eq!(ids(&queue), expected);
+        assert_eq!(queue.next.as_ref().map(HandleWithSource::id), Some(expected[0]));
+    }
+
+    #[tokio::test]
+    async fn swapping_the_next_entry_keeps_it_upcoming() {
+        let mut driver = Driver::new(Config::default());
+        let mut queue = Queue::new();
+        let next = resolved(&mut driver);
+        let first = pending("a");
+        let (next_id, first_id) = (next.id(), first.id);
+
+        queue.next = Some(next);
+        queue.rest.push_back(QueueEntry::Pending(first));
+
+        assert!(queue.swap(next_id, first_id));
+        assert_eq!(ids(&queue), [first_id, next_id]);
+        assert!(queue.next.is_none());
+
+        assert!(queue.swap(first_id, next_id));
+        assert_eq!(ids(&queue), [next_id, first_id]);
+        assert_eq!(queue.next.as_ref().map(HandleWithSource::id), Some(next_id));
+    }
+
+    #[tokio::test]
+    async fn removing_the_next_entry_preloads_the_following_one() {
+        let mut driver = Driver::new(Config::default());
+        let mut queue = Queue::new();
+        let (next, following) = (resolved(&mut driver), resolved(&mut driver));
+        let first = pending("a");
+        let (next_id, following_id, first_id) = (next.id(), following.id(), first.id);
+
+        queue.next = Some(next);
+        queue.rest.extend([QueueEntry::Resolved(following), QueueEntry::Pending(first)]);
+
+        assert_eq!(queue.remove(next_id).map(|e| e.id()), Some(next_id));
+        assert_eq!(ids(&queue), [following_id, first_id]);
+        assert_eq!(queue.next.as_ref().map(HandleWithSource::id), Some(following_id));
+        assert!(queue.remove(next_id).is_none());
+    }
+
+    #[tokio::test]
+    async fn inserting_before_the_next_entry_empties_next() {
+        let mut driver = Driver::new(Config::default());
+        let mut queue = Queue::new();
+        let next = resolved(&mut driver);
+        let (first, last) = (pending("a"), pending("b"));
+        let expected = vec![first.id, next.id(), last.id];
+
+        queue.next = Some(next);
+        queue.insert(0, QueueEntry::Pending(first));
+        queue.insert(10, QueueEntry::Pending(last));
+
+        assert_eq!(ids(&queue), expected);
+        assert!(queue.next.is_none());
+    }
+
+    #[tokio::test]
+    async fn taking_entries_before_an_index() {
+        let mut driver = Driver::new(Config::default());
+        let mut queue = Queue::new();
+        let (next, third) = (resolved(&mut driver), resolved(&mut driver));
+        let (second, fourth) = (pending("a"), pending("b"));
+        let (next_id, second_id, third_id, fourth_id) = (next.id(), second.id, third.id(), fourth.id);
+
+        queue.next = Some(next);
+        queue.rest.extend([
+            QueueEntry::Pending(second),
+            QueueEntry::Resolved(third),
+            QueueEntry::Pending(fourth)
+        ]);
+
+        assert!(queue.take_before(4).is_none());
+
+        let taken = queue.take_before(2).unwrap();
+        assert_eq!(taken.iter().map(QueueEntry::id).collect::<Vec<_>>(), [next_id, second_id]);
+        assert_eq!(ids(&queue), [third_id, fourth_id]);
+        assert_eq!(queue.next.as_ref().map(HandleWithSource::id), Some(third_id));
+    }
+
+    #[test]
+    fn editing_unknown_entries_fails() {
+        let mut queue = Queue::new();
+        let first = pending("a");
+        let first_id = first.id;
+        queue.rest.push_back(QueueEntry::Pending(first));
+
+        assert!(!queue.move_to(Uuid::new_v4(), 0));
+        assert!(!queue.swap(first_id, Uuid::new_v4()));
+        assert!(queue.remove(Uuid::new_v4()).is_none());
+        assert_eq!(ids(&queue), [first_id]);
+    }
+
+    #[test]
+    fn moving_entries_reorders_the_repeat_backup() {
+        let mut queue = Queue::new();
+        let played = pending("played");
+        let tracks = [pending("a"), pending("b"), pending("c")];
+        let [a, b, c] = tracks.each_ref().map(|t| t.id);
+
+        queue.repeat = RepeatMode::Infinite;
+        queue.backup.push_back(played.clone());
+        queue.backup.extend(tracks.iter().cloned());
+        queue.rest.extend(tracks.map(QueueEntry::Pending));
+
+        assert!(queue.move_to(c, 0));
+        assert_eq!(ids(&queue), [c, a, b]);
+        assert_eq!(queue.backup.iter().map(|t| t.id).collect::<Vec<_>>(), [played.id, c, a, b]);
+        assert_preloaded(&queue);
+    }
+
+    fn sorted(mut ids: Vec<Uuid>) -> Vec<Uuid> {
+        ids.sort();
+        ids
+    }
+
+    #[tokio::test]
+    async fn shuffling_keeps_the_entries_and_the_backup() {
+        let mut driver = Driver::new(Config::default());
+        let mut queue = Queue::new();
+        let next = resolved(&mut driver);
+        let tracks = (0..8).map(|i| pending(&i.to_string())).collect::<Vec<_>>();
+
+        queue.repeat = RepeatMode::Infinite;
+        queue.backup.push_back(next.to_pending().await);
+        queue.backup.extend(tracks.iter().cloned());
+        queue.next = Some(next);
+        queue.rest.extend(tracks.into_iter().map(QueueEntry::Pending));
+        queue.rest.push_back(QueueEntry::Resolved(resolved(&mut driver)));
+
+        let before = ids(&queue);
+        let backup = queue.backup.iter().map(|t| t.id).collect::<Vec<_>>();
+
+        for _ in 0..10 {
+            queue.shuffle();
+
+            assert_eq!(sorted(ids(&queue)), sorted(before.clone()));
+            assert_eq!(queue.backup.iter().map(|t| t.id).collect::<Vec<_>>(), backup);
+            assert_preloaded(&queue);
+        }
+    }
+
+    #[tokio::test]
+    async fn shuffle_mode_picks_a_next_entry_among_the_upcoming_ones() {
+        let mut driver = Driver::new(Config::default());
+        let mut queue = Queue::new();
+        let next = resolved(&mut driver);
+
+        queue.next = Some(next);
+        queue.rest.extend((0..8).map(|i| QueueEntry::Pending(pending(&i.to_string()))));
+        queue.rest.push_back(QueueEntry::Resolved(resolved(&mut driver)));
+
+        let before = ids(&queue);
+        queue.set_shuffle(true);
+
+        assert!(queue.shuffle);
+        assert_eq!(sorted(ids(&queue)), sorted(before));
+        assert_preloaded(&queue);
+
+        queue.next = None;
+        let remaining = ids(&queue);
+        let picked = queue.take_next_entry().map(|e| e.id());
+
+        assert!(picked.is_some_and(|id| remaining.contains(&id)));
+    }
+
+    #[test]
+    fn disabling_shuffle_mode_keeps_the_order() {
+        let mut queue = Queue::new();
+        queue.rest.extend((0..4).map(|i| QueueEntry::Pending(pending(&i.to_string()))));
+        let before = ids(&queue);
+
+        queue.set_shuffle(false);
+
+        assert!(!queue.shuffle);
+        assert_eq!(ids(&queue), before);
+        assert_eq!(queue.take_next_entry().map(|e| e.id()), Some(before[0]));
+    }
+}
@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tokio::task::AbortHandle;
+
+/// Maximum duration of a sleep timer in milliseconds, a day.
+const MAX_DURATION: u64 = 24 * 60 * 60 * 1000;
+
+/// When a sleep timer stops the playback.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SleepTimerMode {
+    /// After the given time in milliseconds.
+    Duration {
+        duration: u64
+    },
+    /// After the current track and the given number of tracks after it have ended.
+    Tracks {
+        tracks: u32
+    }
+}
+
+/// Sleep timer settings of a player.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug)]
+pub struct SleepTimer {
+    #[serde(flatten)]
+    pub mode: SleepTimerMode,
+    /// Whether to leave the voice channel once the playback is stopped.
+    #[serde(default)]
+    pub disconnect: bool,
+    /// Whether to clear the queue once the playback is stopped, otherwise it is kept and the
+    /// next track plays once resumed.
+    #[serde(default)]
+    pub clear_queue: bool
+}
+
+impl SleepTimer {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if let SleepTimerMode::Duration { duration } = self.mode {
+            if !(1..=MAX_DURATION).contains(&duration) {
+                return Err("Sleep timer duration must be between 1 and 86400000ms");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Time before the timer fires, if it counts time instead of tracks.
+    pub fn duration(&self) -> Option<Duration> {
+        match self.mode {
+            SleepTimerMode::Duration { duration } => Some(Duration::from_millis(duration)),
+            SleepTimerMode::Tracks { .. } => None
+        }
+    }
+}
+
+/// A sleep timer counting down, the task waiting for its duration is aborted once dropped.
+pub struct ActiveSleepTimer {
+    timer: SleepTimer,
+    deadline: Option<Instant>,
+    task: Option<AbortHandle>
+}
+
+impl ActiveSleepTimer {
+    /// Starts counting down, `task` fires the timer once its duration has elapsed.
+    pub fn new(timer: SleepTimer, task: Option<AbortHandle>) -> Self {
+        Self {
+            timer,
+            deadline: timer.duration().map(|d| Instant::now() + d),
+            task
+        }
+    }
+
+    pub fn timer(&self) -> SleepTimer {
+        self.timer
+    }
+
+    /// Whether if the timer fires once the current track ends.
+    pub fn is_last_track(&self) -> bool {
+        matches!(self.timer.mode, SleepTimerMode::Tracks { tracks: 0 })
+    }
+
+    /// Counts a track that ended, returns whether if the timer fires.
+    pub fn track_ended(&mut self) -> bool {
+        match &mut self.timer.mode {
+            SleepTimerMode::Tracks { tracks: 0 } => true,
+            SleepTimerMode::Tracks { tracks } => {
+                *tracks -= 1;
+                false
+            },
+            SleepTimerMode::Duration { .. } => false
+        }
+    }
+
+    /// The settings of the timer with the time or tracks left before it fires.
+    pub fn remaining(&self) -> SleepTimer {
+        let mode = match (self.timer.mode, self.deadline) {
+            (SleepTimerMode::Duration { .. }, Some(deadline)) => SleepTimerMode::Duration {
+                duration: deadline.saturating_duration_since(Instant::now()).as_millis() as u64
+            },
+            (mode, _) => mode
+        };
+
+        SleepTimer {
+            mode,
+            ..self.timer
+        }
+    }
+}
+
+impl Drop for ActiveSleepTimer {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel};
+use uuid::Uuid;
+
+/// Duration of a mixer frame in songbird, the next track is started this early in gapless
+/// mode so it begins on the same frame the current one ends.
+const MIX_FRAME: Duration = Duration::from_millis(20);
+/// Maximum crossfade duration in milliseconds.
+const MAX_CROSSFADE: u64 = 20_000;
+
+fn default_duration() -> u64 {
+    5000
+}
+
+/// How a player moves from a track to the next one in queue.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionMode {
+    /// The next track starts after the current one has ended.
+    #[default]
+    Off,
+    /// The next track starts exactly when the current one ends, without silence between them.
+    Gapless,
+    /// The next track fades in while the current one fades out.
+    Crossfade
+}
+
+/// Transition settings of a player.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Transition {
+    #[serde(default)]
+    pub mode: TransitionMode,
+    /// Duration of the crossfade in milliseconds.
+    #[serde(default = "default_duration")]
+    pub duration: u64
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self {
+            mode: TransitionMode::Off,
+            duration: default_duration()
+        }
+    }
+}
+
+impl Transition {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.mode == TransitionMode::Crossfade && !(1..=MAX_CROSSFADE).contains(&self.duration) {
+            return Err("Crossfade duration must be between 1 and 20000ms");
+        }
+
+        Ok(())
+    }
+
+    /// Time before the end of a track the transition to the next one starts.
+    pub fn lead(&self) -> Option<Duration> {
+        match self.mode {
+            TransitionMode::Off => None,
+            TransitionMode::Gapless => Some(MIX_FRAME),
+            TransitionMode::Crossfade => Some(Duration::from_millis(self.duration))
+        }
+    }
+
+    /// Duration of the fades applied to both tracks.
+    pub fn fade(&self) -> Duration {
+        match self.mode {
+            TransitionMode::Crossfade => Duration::from_millis(self.duration),
+            _ => Duration::ZERO
+        }
+    }
+}
+
+/// Transition settings of a player, shared with the sources of its tracks so they can
+/// tell the player when they are about to end.
+pub struct Transitions {
+    transition: RwLock<Transition>,
+    sender: UnboundedSender<Uuid>
+}
+
+impl Transitions {
+    pub fn new() -> (Self, UnboundedReceiver<Uuid>) {
+        let (sender, receiver) = unbounded_channel();
+
+        (Self {
+            transition: RwLock::new(Transition::default()),
+            sender
+        }, receiver)
+    }
+
+    pub fn get(&self) -> Transition {
+        self.transition.read().clone()
+    }
+
+    pub fn set(&self, transition: Transition) {
+        *self.transition.write() = transition;
+    }
+
+    /// Time before the end of a track its source must call [`ending`](Self::ending).
+    pub fn lead(&self) -> Option<Duration> {
+        self.transition.read().lead()
+    }
+
+    /// Notifies the player the track with the given id is about to end.
+    pub fn ending(&self, track: Uuid) {
+        let _ = self.sender.send(track);
+    }
+}
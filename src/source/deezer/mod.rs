@@ -12,9 +12,10 @@ use serde_json::Value;
 use songbird::input::{HttpRequest, Input};
 use crate::api::error::IntoResponseError;
 use crate::source::deezer::error::Error;
-use crate::source::deezer::model::{DeezerAlbum, DeezerAlbumData, DeezerPlaylist, DeezerPlaylistData, DeezerTrack, Either3, GetTrackFullResponse, GetUserResponse, ItemData, JwtResponse, RequestTrackBody, Response, ResponseWithErrorAsObjects, SearchResponse, StreamRequest, StreamResponse};
+use crate::api::model::play::PlaySource;
+use crate::source::deezer::model::{Data, DeezerAlbum, DeezerAlbumData, DeezerPlaylist, DeezerPlaylistData, DeezerTrack, Either3, GetTrackFullResponse, GetUserResponse, ItemData, JwtResponse, RequestTrackBody, Response, ResponseWithErrorAsObjects, SearchResponse, StreamRequest, StreamResponse};
 use crate::source::deezer::stream::DeezerHttpStream;
-use crate::source::{Playable, SourcePlayer};
+use crate::source::{ListedTrack, Playable, Recommender, SourcePlayer};
 
 pub mod model;
 pub mod error;
@@ -188,6 +189,27 @@ impl Deezer {
         ))
     }
 
+    /// Tracks of the radio of the artist of the provided track.
+    pub async fn artist_radio(&self, track: usize) -> Result<Vec<DeezerTrack>, Error> {
+        self.maintenance().await?;
+
+        let track = self.http.get(format!("{BASE_API_URL}track/{track}"))
+            .send()
+            .await?
+            .json::<Response<ItemData>>()
+            .await?
+            .into_result()?;
+
+        let radio = self.http.get(format!("{BASE_API_URL}artist/{}/radio", track.artist.id))
+            .send()
+            .await?
+            .json::<Response<Data<Vec<ItemData>>>>()
+            .await?
+            .into_result()?;
+
+        Ok(radio.data.into_iter().map(DeezerTrack::parse).collect())
+    }
+
     fn url_parts(&self, url: &str) -> Option<(ItemType, usize)> {
         let capture = unsafe { self.regexes.get_unchecked(0) }.captures(url)?;
 
@@ -203,6 +225,11 @@ impl Deezer {
         Some((kind, id))
     }
 
+    /// Whether if the url is a link to an album or a playlist.
+    pub fn is_list(&self, url: &str) -> bool {
+        matches!(self.url_parts(url), Some((ItemType::Album | ItemType::Playlist, _)))
+    }
+
     pub async fn get_link(
         &self,
         url: &str
@@ -287,6 +314,25 @@ fn get_key(id: usize) -> String {
     })
 }
 
+/// Recommends the radio of the artist of the played track.
+#[async_trait::async_trait]
+impl Recommender for Deezer {
+    async fn related(&self, source: &PlaySource) -> Result<Option<Vec<ListedTrack>>, IntoResponseError> {
+        let PlaySource::Link { link, .. } = source else {
+            return Ok(None);
+        };
+
+        let Some((ItemType::Track, id)) = self.url_parts(link) else {
+            return Ok(None);
+        };
+
+        Ok(Some(self.artist_radio(id).await?
+            .into_iter()
+            .map(Into::into)
+            .collect()))
+    }
+}
+
 #[async_trait::async_trait]
 impl SourcePlayer for Deezer {
     async fn play_url(&self, url: String) -> Result<Playable, IntoResponseError> {
@@ -301,7 +347,8 @@ impl SourcePlayer for Deezer {
 
                 Ok(Playable {
                     input: Input::Lazy(Box::new(stream)),
-                    meta: t.into()
+                    meta: t.into(),
+                    loudness: None
                 })
             },
             Some(_) => Err(IntoResponseError::new("Non-track url provided")),
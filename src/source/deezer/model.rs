@@ -111,6 +111,8 @@ pub struct ItemData {
 
 #[derive(Deserialize, Debug, Default)]
 pub struct Artist {
+    #[serde(default)]
+    pub id: usize,
     pub name: String
 }
 
@@ -19,7 +19,8 @@ impl SourcePlayer for HttpSource {
     async fn play_url(&self, url: String) -> Result<Playable, IntoResponseError> {
         Ok(Playable {
             input: HttpRequest::new(self.http.clone(), url).into(),
-            meta: Default::default()
+            meta: Default::default(),
+            loudness: None
         })
     }
 }
@@ -3,9 +3,13 @@ use reqwest::Client;
 use songbird::input::{AuxMetadata, Input};
 use crate::api::error::IntoResponseError;
 use crate::api::model::play::PlaySource;
+use crate::api::model::track::Track;
+use crate::ptr::SharedPtr;
 use crate::source::deezer::Deezer;
+use crate::source::deezer::model::{DeezerTrack, Either3};
 use crate::source::http::HttpSource;
 use crate::source::youtube::Youtube;
+use crate::source::youtube::model::YoutubeTrack;
 use crate::source::ytdlp::Ytdlp;
 
 pub mod youtube;
@@ -48,11 +52,47 @@ impl Sources {
         }
     }
 
+    /// Whether if the provided source is a link to a playlist or an album, checked without
+    /// fetching it.
+    pub fn is_list(&self, source: &PlaySource) -> bool {
+        let PlaySource::Link { force_ytdlp: false, link } = source else {
+            return false;
+        };
+
+        self.youtube.is_playlist(link) || self.deezer.is_list(link)
+    }
+
+    /// Gets the tracks of the provided source if it is a link to a playlist or an album,
+    /// returns `None` for any other source.
+    pub async fn tracks_of(&self, source: &PlaySource) -> Result<Option<Vec<ListedTrack>>, IntoResponseError> {
+        let PlaySource::Link { force_ytdlp: false, link } = source else {
+            return Ok(None);
+        };
+
+        if self.youtube.is_playlist(link) {
+            let playlist = self.youtube.playlist(link.clone()).await?;
+            return Ok(Some(playlist.tracks.into_iter().map(Into::into).collect()));
+        }
+
+        if self.deezer.is_list(link) {
+            let tracks = match self.deezer.get_link(link).await? {
+                Either3::A(_) => return Ok(None),
+                Either3::B(playlist) => playlist.tracks,
+                Either3::C(album) => album.tracks
+            };
+
+            return Ok(Some(tracks.into_iter().map(Into::into).collect()));
+        }
+
+        Ok(None)
+    }
+
     pub async fn playable_for(&self, source: &mut PlaySource) -> Result<Playable, IntoResponseError> {
         Ok(match source {
             PlaySource::Bytes {track, bytes} => Playable {
                 input: Input::from(<Bytes as Into<Input>>::into(bytes.clone())),
-                meta: track.clone().map(|t| t.into()).unwrap_or_default()
+                meta: track.clone().map(|t| t.into()).unwrap_or_default(),
+                loudness: None
             },
             other => {
                 let source = self.source_for(other);
@@ -72,7 +112,62 @@ impl Sources {
 
 pub struct Playable {
     pub input: Input,
-    pub meta: AuxMetadata
+    pub meta: AuxMetadata,
+    /// Loudness of the track in LUFS, if the source provides it.
+    pub loudness: Option<f32>
+}
+
+/// A track of a playlist or an album, along with the metadata known before playing it.
+pub struct ListedTrack {
+    pub source: PlaySource,
+    pub track: Track
+}
+
+/// A single track, listed as the track provided in the source, or with only the link of the
+/// source if none is provided, since the rest of the metadata is only known once resolved.
+impl From<PlaySource> for ListedTrack {
+    fn from(mut source: PlaySource) -> Self {
+        let track = source.track().unwrap_or_else(|| Track {
+            source_url: source.link().map(ToString::to_string),
+            ..Track::default()
+        });
+
+        Self {
+            source,
+            track
+        }
+    }
+}
+
+impl From<YoutubeTrack> for ListedTrack {
+    fn from(value: YoutubeTrack) -> Self {
+        Self {
+            source: PlaySource::Link {
+                force_ytdlp: false,
+                link: value.url.clone()
+            },
+            track: Track {
+                artist: value.author,
+                duration: Some(value.length as _),
+                source_url: Some(value.url),
+                title: Some(value.title),
+                thumbnail: value.thumbnail,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+impl From<DeezerTrack> for ListedTrack {
+    fn from(value: DeezerTrack) -> Self {
+        Self {
+            source: PlaySource::Link {
+                force_ytdlp: false,
+                link: value.uri.clone()
+            },
+            track: AuxMetadata::from(value).into()
+        }
+    }
 }
 
 /// Represents players that can play from an internet URL.
@@ -80,3 +175,35 @@ pub struct Playable {
 pub trait SourcePlayer {
     async fn play_url(&self, _url: String) -> Result<Playable, IntoResponseError>;
 }
+
+/// Provides tracks related to a played one, used by the autoplay mode of the players.
+#[async_trait::async_trait]
+pub trait Recommender: Send + Sync {
+    /// Tracks related to the one played from the provided source, or `None` if the source
+    /// isn't supported.
+    async fn related(&self, source: &PlaySource) -> Result<Option<Vec<ListedTrack>>, IntoResponseError>;
+}
+
+#[async_trait::async_trait]
+impl Recommender for Sources {
+    async fn related(&self, source: &PlaySource) -> Result<Option<Vec<ListedTrack>>, IntoResponseError> {
+        let PlaySource::Link { link, .. } = source else {
+            return Ok(None);
+        };
+
+        if self.youtube.can_play(link) {
+            self.youtube.related(source).await
+        } else if self.deezer.can_play(link) {
+            self.deezer.related(source).await
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Recommender + ?Sized> Recommender for SharedPtr<T> {
+    async fn related(&self, source: &PlaySource) -> Result<Option<Vec<ListedTrack>>, IntoResponseError> {
+        (**self).related(source).await
+    }
+}
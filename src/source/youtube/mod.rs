@@ -6,16 +6,21 @@ use rusty_ytdl as ytdl;
 use rusty_ytdl::{RequestOptions, VideoOptions, VideoQuality, VideoSearchOptions};
 use rusty_ytdl::search::{Playlist, PlaylistSearchOptions, SearchOptions, SearchResult, SearchType};
 use songbird::input::{AuxMetadata, HttpRequest};
-use crate::source::{IntoResponseError, Playable, SourcePlayer};
+use crate::api::model::play::PlaySource;
+use crate::source::{IntoResponseError, ListedTrack, Playable, Recommender, SourcePlayer};
 use ytdl::search::YouTube as RustyYoutube;
 use model::*;
 
+/// Loudness YouTube measures the loudness of its videos against, in LUFS.
+const YOUTUBE_REFERENCE_LOUDNESS: f32 = -14.0;
+
 pub struct Youtube {
     search: RustyYoutube,
     video_options: VideoOptions,
     request_options: RequestOptions,
     http: Client,
-    regexes: Box<[Regex]> // We use a boxed slice to add more regexes if needed later
+    regexes: Box<[Regex]>, // We use a boxed slice to add more regexes if needed later
+    playlist_regex: Regex
 }
 
 impl Youtube {
@@ -36,7 +41,8 @@ impl Youtube {
             http,
             regexes: vec![
                 Regex::new(r#"^((?:https?:)?\/\/)?((?:www|m|music)\.)?((?:youtube\.com|youtu.be))(\/(?:[\w\-]+\?v=|embed\/|v\/)?)([\w\-]+)(\S+)?$"#).unwrap(),
-            ].into_boxed_slice()
+            ].into_boxed_slice(),
+            playlist_regex: Regex::new(r#"^(?:https?://)?(?:(?:www|m|music)\.)?youtube\.com/playlist\?(?:\S*&)?list=[\w\-]+"#).unwrap()
         }
     }
 
@@ -50,6 +56,11 @@ impl Youtube {
         false
     }
 
+    /// Whether if the url is a link to a playlist, links to videos of a playlist aren't.
+    pub fn is_playlist(&self, url: &str) -> bool {
+        self.playlist_regex.is_match(url)
+    }
+
     pub async fn search_videos(
         &self,
         query: String,
@@ -101,6 +112,29 @@ impl Youtube {
     }
 }
 
+/// Recommends the videos YouTube shows next to the played one.
+#[async_trait::async_trait]
+impl Recommender for Youtube {
+    async fn related(&self, source: &PlaySource) -> Result<Option<Vec<ListedTrack>>, IntoResponseError> {
+        let PlaySource::Link { link, .. } = source else {
+            return Ok(None);
+        };
+
+        if self.is_playlist(link) {
+            return Ok(None);
+        }
+
+        let video = ytdl::Video::new_with_options(link.clone(), self.video_options.clone())?;
+        let info = video.get_basic_info().await?;
+
+        Ok(Some(info.related_videos
+            .into_iter()
+            .filter(|v| !v.is_live)
+            .map(|v| YoutubeTrack::from(v).into())
+            .collect()))
+    }
+}
+
 #[async_trait::async_trait]
 impl SourcePlayer for Youtube {
     async fn play_url(&self, url: String) -> Result<Playable, IntoResponseError> {
@@ -109,6 +143,7 @@ impl SourcePlayer for Youtube {
 
         let mut format = ytdl::choose_format(info.formats.as_slice(), &self.video_options)?;
         let url = std::mem::take(&mut format.url); // only used to create input
+        let loudness = format.loudness_db.map(|db| YOUTUBE_REFERENCE_LOUDNESS + db as f32);
 
         let meta = AuxMetadata::try_from(WrapInfo(info.video_details, format))?;
 
@@ -116,7 +151,8 @@ impl SourcePlayer for Youtube {
 
         Ok(Playable {
             input: req.into(),
-            meta
+            meta,
+            loudness
         })
     }
 }
@@ -1,6 +1,6 @@
 use std::time::Duration;
 use rusty_ytdl::search::{Playlist, Video};
-use rusty_ytdl::{VideoDetails, VideoFormat};
+use rusty_ytdl::{RelatedVideo, VideoDetails, VideoFormat};
 use serde::Serialize;
 use songbird::input::AuxMetadata;
 use crate::ext::VecExt;
@@ -47,6 +47,19 @@ impl From<Video> for YoutubeTrack {
     }
 }
 
+impl From<RelatedVideo> for YoutubeTrack {
+    fn from(mut video: RelatedVideo) -> Self {
+        YoutubeTrack {
+            title: video.title,
+            author: video.author.map(|a| a.name),
+            length: video.length_seconds * 1000,
+            video_id: video.id,
+            url: video.url,
+            thumbnail: video.thumbnails.remove_optional(0).map(|t| t.url)
+        }
+    }
+}
+
 impl From<Playlist> for YoutubePlaylist {
     fn from(mut playlist: Playlist) -> Self {
         YoutubePlaylist {
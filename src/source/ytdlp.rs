@@ -21,7 +21,8 @@ impl SourcePlayer for Ytdlp {
 
         Ok(Playable {
             meta: ydl.aux_metadata().await?,
-            input: ydl.into()
+            input: ydl.into(),
+            loudness: None
         })
     }
 }